@@ -1,4 +1,8 @@
-use std::{fs::*, io::{Read, Write}};
+use std::{fs::*, io::{Read, Write}, path::Path};
+
+#[allow(dead_code)]
+#[path = "src/po.rs"]
+mod po;
 
 static DEBUG_BUILD_ALGO: &str = r#"
 /// Only the debug server build will accept messages marked with codes generated with this function.
@@ -19,12 +23,158 @@ fn read_production_build_algo() -> Option<String> {
     Some(content)
 }
 
+/// Functions whose first argument is a message id, with the context of their messages
+const TRANSLATION_FUNCTIONS: &[(&str, Option<&str>)] = &[("t", None), ("tf", None), ("tn", None), ("tord", Some(po::ORDINAL_CONTEXT))];
+
+/// The language of the message ids, which doesn't need translations
+const SOURCE_LOCALE: &str = "fr";
+
+/// Find the message ids passed as literals to translation functions
+fn collect_message_ids(source: &str, ids: &mut Vec<(Option<&'static str>, String)>) {
+    for (function, context) in TRANSLATION_FUNCTIONS {
+        let pattern = format!("{function}(");
+        for (i, _) in source.match_indices(&pattern) {
+            let preceded_by_identifier = source[..i].chars().next_back().map(|c| c.is_alphanumeric() || c == '_' || c == '.' || c == ':').unwrap_or(false);
+            if preceded_by_identifier {
+                continue;
+            }
+            let Some(literal) = source[i + pattern.len()..].trim_start().strip_prefix('"') else { continue };
+            let mut id = String::new();
+            let mut chars = literal.chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' => break,
+                    '\\' => match chars.next() {
+                        Some('n') => id.push('\n'),
+                        Some(c) => id.push(c),
+                        None => break,
+                    },
+                    c => id.push(c),
+                }
+            }
+            ids.push((*context, id));
+        }
+    }
+}
+
+/// Find the text nodes of the templates passed to `template_html!`, which yew-template looks up in the catalogs
+fn collect_template_texts(source: &str, ids: &mut Vec<(Option<&'static str>, String)>) {
+    for (i, _) in source.match_indices("template_html!") {
+        let rest = source[i + "template_html!".len()..].trim_start();
+        let Some(rest) = rest.strip_prefix(['(', '{']) else { continue };
+        let Some(rest) = rest.trim_start().strip_prefix('"') else { continue };
+        let Some(end) = rest.find('"') else { continue };
+        let path = &rest[..end];
+        let template = read_to_string(path).unwrap_or_else(|e| panic!("Failed to read template {path}: {e}"));
+
+        let mut template = template.as_str();
+        let mut texts = Vec::new();
+        while let Some(start) = template.find('<') {
+            texts.push(&template[..start]);
+            let rest = &template[start..];
+            let end = if rest.starts_with("<!--") {
+                rest.find("-->").map(|e| e + 3)
+            } else if rest.starts_with("<script") || rest.starts_with("<style") {
+                let tag = if rest.starts_with("<script") { "</script>" } else { "</style>" };
+                rest.find(tag).map(|e| e + tag.len())
+            } else {
+                rest.find('>').map(|e| e + 1)
+            };
+            template = &rest[end.unwrap_or(rest.len())..];
+        }
+        texts.push(template);
+
+        for text in texts {
+            let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+            let mut letters = text.as_str();
+            let mut has_letters = false;
+            while !letters.is_empty() {
+                if let Some(rest) = letters.strip_prefix("{{") {
+                    letters = rest.split_once("}}").map(|(_, rest)| rest).unwrap_or("");
+                } else if let Some(rest) = letters.strip_prefix('&') {
+                    letters = rest.split_once(';').map(|(_, rest)| rest).unwrap_or("");
+                } else {
+                    let mut chars = letters.chars();
+                    has_letters |= chars.next().map(|c| c.is_alphabetic()).unwrap_or(false);
+                    letters = chars.as_str();
+                }
+            }
+            if has_letters {
+                ids.push((None, text));
+            }
+        }
+    }
+}
+
+fn collect_sources(dir: &Path, sources: &mut Vec<String>) {
+    for entry in read_dir(dir).expect("Failed to read source directory").flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_sources(&path, sources);
+        } else if path.extension().map(|e| e == "rs").unwrap_or(false) {
+            sources.push(read_to_string(&path).expect("Failed to read source file"));
+        }
+    }
+}
+
+/// Embed the catalogs of the `locales` directory, and fail if they don't translate a message of the sources or templates
+fn embed_catalogs() {
+    println!("cargo:rerun-if-changed=locales");
+    println!("cargo:rerun-if-changed=src");
+
+    let mut locales = read_dir("locales").expect("Failed to read locales directory")
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().map(|e| e == "po").unwrap_or(false))
+        .collect::<Vec<_>>();
+    locales.sort();
+
+    let mut sources = Vec::new();
+    collect_sources(Path::new("src"), &mut sources);
+    let mut ids = Vec::new();
+    for source in &sources {
+        collect_message_ids(source, &mut ids);
+        collect_template_texts(source, &mut ids);
+    }
+    ids.sort();
+    ids.dedup();
+
+    let mut missing = Vec::new();
+    let mut generated = String::from("/// Catalogs of the `locales` directory, by locale\nconst CATALOG_SOURCES: &[(&str, &str)] = &[\n");
+    for path in &locales {
+        let locale = path.file_stem().unwrap().to_string_lossy();
+        let content = read_to_string(path).expect("Failed to read catalog");
+        let catalog = po::Catalog::parse(&content).unwrap_or_else(|e| panic!("Invalid catalog {}: {e}", path.display()));
+        if locale != SOURCE_LOCALE {
+            for (context, id) in &ids {
+                if !catalog.messages.contains_key(&(context.map(String::from), id.clone())) {
+                    missing.push(format!("{}: {id:?}", path.display()));
+                }
+            }
+        }
+        let absolute_path = canonicalize(path).expect("Failed to resolve catalog path");
+        generated.push_str(&format!("    ({locale:?}, include_str!({:?})),\n", absolute_path.display().to_string()));
+    }
+    generated.push_str("];\n");
+    if !missing.is_empty() {
+        panic!("Missing translations:\n{}", missing.join("\n"));
+    }
+
+    let out_dir = std::env::var("OUT_DIR").expect("OUT_DIR isn't set");
+    write(Path::new(&out_dir).join("catalogs.rs"), generated).expect("Failed to write catalogs.rs");
+}
+
 fn main() {
     println!("cargo:rerun-if-changed=src/api/gen_code_prod.rs");
 
     let algo = read_production_build_algo().unwrap_or_else(|| DEBUG_BUILD_ALGO.to_string());
 
-    let mut output_file = File::create("src/api/gen_code.rs").expect("Failed to open src/api/gen_code.rs");
-    output_file.write_all(b"//! Automatically generated!\n//! See build.rs\n\n").expect("Failed to write to src/api/gen_code.rs");
-    output_file.write_all(algo.as_bytes()).expect("Failed to write to src/api/gen_code.rs");
+    // Only write when needed, as the catalogs check is run again whenever src changes
+    let content = format!("//! Automatically generated!\n//! See build.rs\n\n{algo}");
+    if read_to_string("src/api/gen_code.rs").ok().as_deref() != Some(content.as_str()) {
+        let mut output_file = File::create("src/api/gen_code.rs").expect("Failed to open src/api/gen_code.rs");
+        output_file.write_all(content.as_bytes()).expect("Failed to write to src/api/gen_code.rs");
+    }
+
+    embed_catalogs();
 }
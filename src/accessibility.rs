@@ -0,0 +1,86 @@
+//! Accessibility preferences of the settings page: text size, reduced motion and high contrast.
+//!
+//! They are applied as attributes of the root element, such as `data-text-size="large"`, which the stylesheets rely on.
+//! This module also has helpers for keyboard and screen reader users.
+
+use crate::prelude::*;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TextSize {
+    Small = 0,
+    Normal,
+    Large,
+    ExtraLarge,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Motion {
+    /// Follow the `prefers-reduced-motion` preference of the system
+    Auto = 0,
+    Reduced,
+    Full,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Contrast {
+    Normal = 0,
+    High,
+}
+
+fn set_root_attribute(name: &str, value: &str) {
+    let html = window().doc().first_element_child().unwrap();
+    html.set_attribute(name, value).unwrap();
+}
+
+pub fn apply_text_size(text_size: &TextSize) {
+    set_root_attribute("data-text-size", &text_size.to_value_string());
+}
+
+pub fn apply_motion(motion: &Motion) {
+    set_root_attribute("data-motion", &motion.to_value_string());
+}
+
+pub fn apply_contrast(contrast: &Contrast) {
+    set_root_attribute("data-contrast", &contrast.to_value_string());
+    // Events are redrawn with their new colors
+    COLORS_CHANGED.store(true, Ordering::Relaxed);
+}
+
+/// Whether animations should be skipped
+pub fn reduced_motion() -> bool {
+    match SETTINGS.motion.get() {
+        Motion::Auto => match window().match_media("(prefers-reduced-motion: reduce)") {
+            Ok(Some(query)) => query.matches(),
+            _ => false,
+        },
+        Motion::Reduced => true,
+        Motion::Full => false,
+    }
+}
+
+pub fn high_contrast() -> bool {
+    SETTINGS.contrast.get() == Contrast::High
+}
+
+/// Keyboard handler of the elements that act as buttons, clicking them when Enter or Space is pressed
+pub fn click_on_enter(event: KeyboardEvent) {
+    if event.key() != "Enter" && event.key() != " " {
+        return;
+    }
+    event.prevent_default();
+    if let Some(el) = event.target().and_then(|t| t.dyn_into::<HtmlElement>().ok()) {
+        el.click();
+    }
+}
+
+/// Have screen readers read a message, such as the name of the page that was opened
+pub fn announce(message: &str) {
+    let Some(announcer) = window().doc().get_element_by_id("announcer") else { return };
+    // The content is emptied first so that the same message is read again
+    announcer.set_text_content(None);
+    let message = message.to_string();
+    spawn_local(async move {
+        sleep(Duration::from_millis(100)).await;
+        announcer.set_text_content(Some(&message));
+    });
+}
@@ -0,0 +1,81 @@
+//! Export of all the data of the user, and deletion of their account.
+
+use crate::prelude::*;
+
+/// Local storage entries that are credentials rather than data, and are left out of exports
+const CREDENTIALS: &[&str] = &["api_key", "counter"];
+
+/// Gather the data of the user into a ZIP archive.
+/// The comments, survey answers, friends, colors and settings stored by the server are in `server/`,
+/// and everything this device keeps, from settings to cached schedules, is in `device/`.
+/// When the server data can't be exported, the archive only has the data of this device and the error is returned with it.
+pub async fn export_data() -> (Vec<u8>, Option<ApiError>) {
+    let mut files = Vec::new();
+    let local_storage = window().local_storage().unwrap().unwrap();
+    for i in 0..local_storage.length().unwrap_or(0) {
+        let Ok(Some(key)) = local_storage.key(i) else { continue };
+        if CREDENTIALS.contains(&key.as_str()) {
+            continue;
+        }
+        let Ok(Some(value)) = local_storage.get_item(&key) else { continue };
+        let file = match serde_json::from_str::<serde_json::Value>(&value) {
+            Ok(json) if json.is_object() || json.is_array() => (format!("device/{key}.json"), serde_json::to_vec_pretty(&json).unwrap()),
+            _ => (format!("device/{key}.txt"), value.into_bytes()),
+        };
+        files.push(file);
+    }
+
+    // The same format as the settings export, so that it can be imported on another device
+    files.push((String::from("settings.json"), SETTINGS.export().into_bytes()));
+
+    // The data of this device is still exported when the server can't be reached
+    let server_error = match api_get::<serde_json::Map<String, serde_json::Value>>("account/export").await {
+        Ok(server_data) => {
+            files.extend(server_data.into_iter().map(|(name, value)| (format!("server/{name}.json"), serde_json::to_vec_pretty(&value).unwrap())));
+            None
+        }
+        Err(e) => Some(e),
+    };
+
+    (zip(&files, Local::now().naive_local()), server_error)
+}
+
+/// Delete the Cache Storage of the service worker
+async fn clear_caches() -> Result<(), JsValue> {
+    let caches = Reflect::get(&window(), &"caches".into())?;
+    if caches.is_undefined() {
+        return Ok(());
+    }
+    let keys: Function = Reflect::get(&caches, &"keys".into())?.dyn_into()?;
+    let delete: Function = Reflect::get(&caches, &"delete".into())?.dyn_into()?;
+    let names = JsFuture::from(keys.call0(&caches)?.dyn_into::<js_sys::Promise>()?).await?;
+    for name in Array::from(&names).iter() {
+        JsFuture::from(delete.call1(&caches, &name)?.dyn_into::<js_sys::Promise>()?).await?;
+    }
+    Ok(())
+}
+
+/// Remove everything the app stored on this device: storage, caches, scheduled reminders and push subscription
+async fn wipe_device() {
+    let window = window();
+    window.local_storage().unwrap().unwrap().clear().unwrap();
+    if let Ok(Some(session_storage)) = window.session_storage() {
+        let _ = session_storage.clear();
+    }
+
+    // Reminders are disabled now that the storage is empty
+    update_reminders(Rc::new(Vec::new())).await;
+    if let Err(e) = forget_push_subscription().await {
+        sentry_report(e);
+    }
+    if let Err(e) = clear_caches().await {
+        sentry_report(e);
+    }
+}
+
+/// Delete the account on the server, then wipe this device
+pub async fn delete_account() -> Result<(), ApiError> {
+    api_delete::<()>("account").await?;
+    wipe_device().await;
+    Ok(())
+}
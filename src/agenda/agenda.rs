@@ -1,23 +1,106 @@
 use crate::{prelude::*, slider};
 
-fn format_day(day_name: Weekday, day: u32) -> String {
-    let day_name = t(match day_name {
-        Weekday::Mon => "Lundi",
-        Weekday::Tue => "Mardi",
-        Weekday::Wed => "Mercredi",
-        Weekday::Thu => "Jeudi",
-        Weekday::Fri => "Vendredi",
-        Weekday::Sat => "Samedi",
-        Weekday::Sun => "Dimanche",
-    });
-
-    format!("{} {}", day_name, day)
+/// Assign a column to each time range so that overlapping ranges are displayed side by side.
+/// Returns the column and the number of columns of each range, in input order.
+fn overlap_layout(ranges: &[(u64, u64)]) -> Vec<(usize, usize)> {
+    let mut order = (0..ranges.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| ranges[i]);
+
+    let mut layout = vec![(0, 1); ranges.len()];
+    let mut group: Vec<usize> = Vec::new();
+    let mut group_end = 0;
+    let mut column_ends: Vec<u64> = Vec::new();
+    for i in order {
+        let (start, end) = ranges[i];
+
+        // Ranges that don't overlap with the current group start a new one
+        if !group.is_empty() && start >= group_end {
+            for j in group.drain(..) {
+                layout[j].1 = column_ends.len();
+            }
+            column_ends.clear();
+            group_end = 0;
+        }
+
+        let column = match column_ends.iter().position(|&column_end| column_end <= start) {
+            Some(column) => column,
+            None => {
+                column_ends.push(0);
+                column_ends.len() - 1
+            }
+        };
+        column_ends[column] = end;
+        layout[i].0 = column;
+        group.push(i);
+        group_end = group_end.max(end);
+    }
+    for j in group {
+        layout[j].1 = column_ends.len();
+    }
+
+    layout
+}
+
+/// Delay between two updates of the current time indicator, in milliseconds
+const TICK_INTERVAL: i32 = 60 * 1000;
+
+fn format_countdown(seconds: u64) -> String {
+    let minutes = (seconds + 59) / 60;
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{} {minutes} min", t("dans")),
+        (hours, minutes) => format!("{} {hours}h{minutes:02}", t("dans")),
+    }
+}
+
+/// Describe the ongoing or next class, as a title and a detail line
+fn next_class_summary(events: &[RawEvent], now: u64) -> (String, String) {
+    let first = events.partition_point(|e| e.end_unixtime <= now);
+    let Some(event) = events[first..].iter().find(|e| e.end_unixtime > now) else {
+        return (t("Aucun cours à venir").to_string(), String::new())
+    };
+    let location = event.format_location().map(|l| format!(" · {l}")).unwrap_or_default();
+    if event.start_unixtime <= now {
+        let end = user_tz().timestamp_opt(event.end_unixtime as i64, 0).unwrap();
+        let title = format!("{} {}", t("En cours jusqu'à"), format_time(end));
+        (title, format!("{}{location}", event.format_name()))
+    } else {
+        let start = user_tz().timestamp_opt(event.start_unixtime as i64, 0).unwrap();
+        let current = user_tz().timestamp_opt(now as i64, 0).unwrap();
+        let start_str = match start.date_naive() == current.date_naive() {
+            true => format_time(start),
+            false => format_relative_date_time(start, current),
+        };
+        let title = format!("{} {start_str}", t("Libre jusqu'à"));
+        let detail = format!("{}{location} · {}", event.format_name(), format_countdown(event.start_unixtime - now));
+        (title, detail)
+    }
+}
+
+/// How the schedule given for comparison is displayed
+#[derive(Clone, Copy, PartialEq)]
+pub enum CompareMode {
+    Off,
+    /// Each schedule gets its own half of the day
+    Lanes,
+    /// The compared schedule is drawn translucently on top
+    Overlay,
+}
+
+/// Whether two courses are the same class
+fn same_course(a: &RawEvent, b: &RawEvent) -> bool {
+    a.eid == b.eid || (a.start_unixtime == b.start_unixtime && a.end_unixtime == b.end_unixtime && a.summary == b.summary)
 }
 
 pub struct Agenda {
     selected_day: NaiveDate,
     slider: Rc<RefCell<slider::SliderManager>>,
     counter: AtomicUsize,
+    /// Personal event currently opened in the editor
+    editing: Option<PersonalEvent>,
+    compare_mode: CompareMode,
+    /// Current time, updated every minute
+    now: i64,
+    tick_interval: Option<i32>,
 }
 
 pub enum AgendaMsg {
@@ -25,7 +108,12 @@ pub enum AgendaMsg {
     Next,
     Goto{ day: u32, month: u32, year: i32 },
     Refresh,
-    PushColors,
+    PushSettings,
+    CreatePersonalEvent { day_start: u64, event: MouseEvent },
+    EditPersonalEvent(String),
+    CloseEditor,
+    NextCompareMode,
+    Tick,
     AppMsg(AppMsg),
 }
 
@@ -33,6 +121,19 @@ pub enum AgendaMsg {
 pub struct AgendaProps {
     pub app_link: AppLink,
     pub events: Rc<Vec<RawEvent>>,
+    #[prop_or_default]
+    pub imported_events: Rc<Vec<ImportedEvent>>,
+    #[prop_or_default]
+    pub personal_events: Rc<Vec<PersonalEvent>>,
+    /// Another schedule to compare the displayed one with
+    #[prop_or_default]
+    pub compared_events: Option<Rc<Vec<RawEvent>>>,
+    /// Whether the displayed schedule is outdated and being fetched again
+    #[prop_or_default]
+    pub refreshing: bool,
+    /// Whether changes of settings or colors are waiting to be synced
+    #[prop_or_default]
+    pub syncing: bool,
     pub popup: Option<(RawEvent, bool, Option<usize>)>,
     pub profile_src: Option<String>,
     pub user_info: Rc<Option<UserInfo>>,
@@ -45,6 +146,11 @@ impl PartialEq for AgendaProps {
     fn eq(&self, other: &Self) -> bool {
         !COLORS_CHANGED.load(Ordering::Relaxed)
             && self.events == other.events
+            && self.imported_events == other.imported_events
+            && self.personal_events == other.personal_events
+            && self.compared_events == other.compared_events
+            && self.refreshing == other.refreshing
+            && self.syncing == other.syncing
             && self.popup == other.popup
             && self.user_info == other.user_info
             && self.comment_counts == other.comment_counts
@@ -58,12 +164,12 @@ impl Component for Agenda {
 
     fn create(ctx: &Context<Self>) -> Self {
         let now = chrono::Local::now();
-        let now = now.with_timezone(&Paris);
+        let now = now.with_timezone(&user_tz());
 
         // Trigger color sync when page is closed
         let link = ctx.link().clone();
         let unload = Closure::wrap(Box::new(move |_: web_sys::Event| {
-            link.send_message(AgendaMsg::PushColors);
+            link.send_message(AgendaMsg::PushSettings);
         }) as Box<dyn FnMut(_)>);
         window().add_event_listener_with_callback("unload", unload.as_ref().unchecked_ref()).unwrap();
         unload.forget();
@@ -73,17 +179,31 @@ impl Component for Agenda {
 
         // Auto-push colors every 15s if needed
         let link = ctx.link().clone();
-        let push_colors = Closure::wrap(Box::new(move || {
-            link.send_message(AgendaMsg::PushColors);
+        let push_settings = Closure::wrap(Box::new(move || {
+            link.send_message(AgendaMsg::PushSettings);
         }) as Box<dyn FnMut()>);
-        if let Err(e) = window().set_interval_with_callback_and_timeout_and_arguments(push_colors.as_ref().unchecked_ref(), 1000*15, &Array::new()) {
+        if let Err(e) = window().set_interval_with_callback_and_timeout_and_arguments(push_settings.as_ref().unchecked_ref(), 1000*15, &Array::new()) {
             sentry_report(JsValue::from(&format!("Failed to set timeout: {:?}", e)));
         }
-        push_colors.forget();
+        push_settings.forget();
+
+        // Move the current time indicator every minute
+        let link = ctx.link().clone();
+        let tick = Closure::wrap(Box::new(move || {
+            link.send_message(AgendaMsg::Tick);
+        }) as Box<dyn FnMut()>);
+        let tick_interval = match window().set_interval_with_callback_and_timeout_and_arguments(tick.as_ref().unchecked_ref(), TICK_INTERVAL, &Array::new()) {
+            Ok(id) => Some(id),
+            Err(e) => {
+                sentry_report(JsValue::from(&format!("Failed to set interval: {:?}", e)));
+                None
+            }
+        };
+        tick.forget();
 
         // Switch to next day if it's late or to monday if it's weekend
         let weekday = now.weekday();
-        let has_event = has_event_on_day(&ctx.props().events, now.date_naive(), Weekday::Sat);
+        let has_event = has_event_on_day(&ctx.props().events, &ctx.props().imported_events, now.date_naive(), Weekday::Sat);
         if now.hour() >= 19 || weekday == Weekday::Sun || (weekday == Weekday::Sat && !has_event) {
             let link2 = ctx.link().clone();
             spawn_local(async move {
@@ -102,6 +222,10 @@ impl Component for Agenda {
             selected_day: now.date_naive(),
             slider,
             counter: AtomicUsize::new(0),
+            editing: None,
+            compare_mode: CompareMode::Off,
+            now: now(),
+            tick_interval,
         }
     }
 
@@ -111,7 +235,7 @@ impl Component for Agenda {
                 let day_prev_week = self.selected_day - chrono::Duration::days(7);
                 if self.selected_day.weekday() != Weekday::Mon {
                     self.selected_day -= chrono::Duration::days(1);
-                } else if self.selected_day.weekday() == Weekday::Mon && !has_event_on_day(&ctx.props().events, day_prev_week, Weekday::Sat) {
+                } else if self.selected_day.weekday() == Weekday::Mon && !has_event_on_day(&ctx.props().events, &ctx.props().imported_events, day_prev_week, Weekday::Sat) {
                     self.selected_day -= chrono::Duration::days(3);
                 } else {
                     self.selected_day -= chrono::Duration::days(2);
@@ -123,7 +247,7 @@ impl Component for Agenda {
                 let day_this_week = self.selected_day;
                 if self.selected_day.weekday() == Weekday::Sat {
                     self.selected_day += chrono::Duration::days(2);
-                } else if self.selected_day.weekday() == Weekday::Fri && !has_event_on_day(&ctx.props().events, day_this_week, Weekday::Sat) {
+                } else if self.selected_day.weekday() == Weekday::Fri && !has_event_on_day(&ctx.props().events, &ctx.props().imported_events, day_this_week, Weekday::Sat) {
                     self.selected_day += chrono::Duration::days(3);
                 } else {
                     self.selected_day += chrono::Duration::days(1);
@@ -157,10 +281,50 @@ impl Component for Agenda {
                 }
                 true
             },
-            AgendaMsg::PushColors => {
+            AgendaMsg::PushSettings => {
                 crate::COLORS.push_colors();
+                SETTINGS_SYNC.push(ctx.props().app_link.clone());
                 false
             },
+            AgendaMsg::CreatePersonalEvent { day_start, event } => {
+                // Only react to clicks on empty slots, not on events
+                let Some(day_el) = event.target().and_then(|t| t.dyn_into::<web_sys::Element>().ok()) else { return false };
+                if day_el.class_name() != "day" || ctx.props().profile_src.is_some() {
+                    return false;
+                }
+                let ratio = event.offset_y() as f64 / day_el.client_height().max(1) as f64;
+
+                // Round to the previous quarter of an hour
+                let start = day_start + 8 * 3600 + (ratio * 43200.0) as u64;
+                let start = start - start % 900;
+                self.editing = Some(PersonalEvent::new(start));
+                self.slider.borrow_mut().disable();
+                true
+            }
+            AgendaMsg::EditPersonalEvent(id) => {
+                self.editing = ctx.props().personal_events.iter().find(|e| e.id == id && !e.deleted).cloned();
+                self.slider.borrow_mut().disable();
+                self.editing.is_some()
+            }
+            AgendaMsg::CloseEditor => {
+                self.editing = None;
+                if ctx.props().popup.is_none() {
+                    self.slider.borrow_mut().enable();
+                }
+                true
+            }
+            AgendaMsg::NextCompareMode => {
+                self.compare_mode = match self.compare_mode {
+                    CompareMode::Off => CompareMode::Lanes,
+                    CompareMode::Lanes => CompareMode::Overlay,
+                    CompareMode::Overlay => CompareMode::Off,
+                };
+                true
+            }
+            AgendaMsg::Tick => {
+                self.now = now();
+                true
+            }
             AgendaMsg::AppMsg(msg) => {
                 ctx.props().app_link.send_message(msg);
                 false
@@ -168,8 +332,14 @@ impl Component for Agenda {
         }
     }
 
+    fn destroy(&mut self, _ctx: &Context<Self>) {
+        if let Some(id) = self.tick_interval.take() {
+            window().clear_interval_with_handle(id);
+        }
+    }
+
     fn changed(&mut self, ctx: &Context<Self>, _old_props: &Self::Properties) -> bool {
-        if ctx.props().popup.is_some() {
+        if ctx.props().popup.is_some() || self.editing.is_some() {
             self.slider.borrow_mut().disable();
         } else {
             self.slider.borrow_mut().enable();
@@ -182,43 +352,127 @@ impl Component for Agenda {
         let screen_width = crate::slider::width();
         let mobile = screen_width <= 1000;
         
-        // Go on the first day of the week
+        // Go on the first day of the week, which is only displayed on desktop when it's a Sunday
         let mut current_day = self.selected_day;
+        let day_count = match mobile {
+            true => 6,
+            false => 6 + (first_weekday() == Weekday::Sun) as u32,
+        };
         match mobile {
             true => current_day -= chrono::Duration::days(2),
-            false => for _ in 0..self.selected_day.weekday().num_days_from_monday() {
+            false => for _ in 0..days_from_week_start(self.selected_day.weekday()) {
                 current_day -= chrono::Duration::days(1);
             },
         };
 
+        // Compute the position of the current time from the local time, so that it's right on DST change days
+        let now_local = user_tz().timestamp_opt(self.now, 0).unwrap();
+        let now_offset = now_local.num_seconds_from_midnight() as i64 - 8 * 3600;
+        let now_indicator_top = (0..43200).contains(&now_offset).then(|| 100.0 / 43200.0 * now_offset as f64);
+
         // Build each day and put events in them
         let mut days = Vec::new();
         let mut day_names = Vec::new();
-        for d in 0..6 {
-            let day_start = Paris.from_local_datetime(&current_day.and_hms_opt(0,0,0).unwrap()).unwrap().timestamp() as u64;
+        for d in 0..day_count {
+            let day_start = day_start(current_day);
             let selected_event_other_day = !mobile && ctx.props().popup.as_ref().map(|(e,is_closing,_)| !is_closing && !(day_start..day_start+86400).contains(&e.start_unixtime)).unwrap_or(false);
-            let mut events = Vec::new();
 
-            // Iterate over events, starting from the first one that starts during the current day
-            let mut idx = match ctx.props().events.binary_search_by_key(&day_start, |e| e.start_unixtime) {
-                Ok(idx) => idx,
-                Err(idx) => idx,
+            // Find events that start during the current day
+            let day_range = |start: u64| (day_start..=day_start + 24*3600).contains(&start);
+            let first_course = ctx.props().events.partition_point(|e| e.start_unixtime < day_start);
+            let courses = ctx.props().events[first_course..].iter().take_while(|e| day_range(e.start_unixtime)).collect::<Vec<_>>();
+            let day_end = day_start + 24*3600;
+            let last = ctx.props().imported_events.partition_point(|e| e.start_unixtime < day_end);
+            let imported = ctx.props().imported_events[..last].iter().filter(|e| e.overlaps(&(day_start..day_end))).collect::<Vec<_>>();
+            let personal = ctx.props().personal_events.iter().filter_map(|e| e.occurs_on(current_day).map(|start| AgendaEvent::Personal(e.clone(), start))).collect::<Vec<_>>();
+            let agenda_events = courses.into_iter().map(|e| AgendaEvent::Course(e.clone())).chain(personal).collect::<Vec<_>>();
+
+            // Display overlapping events side by side
+            let ranges = agenda_events.iter().map(|e| (e.start_unixtime(), e.end_unixtime()))
+                .chain(imported.iter().map(|e| match e.all_day {
+                    true => (day_start, day_end),
+                    false => (e.start_unixtime.max(day_start), e.end_unixtime.min(day_end)),
+                }))
+                .collect::<Vec<_>>();
+            let mut layout = overlap_layout(&ranges).into_iter().collect::<Vec<_>>();
+
+            // Find the classes of the compared schedule on that day
+            let compared_events = match (&ctx.props().compared_events, self.compare_mode) {
+                (Some(compared_events), CompareMode::Lanes | CompareMode::Overlay) => compared_events.as_slice(),
+                _ => &[],
             };
-            while let Some(e) = ctx.props().events.get(idx) {
-                if e.start_unixtime > day_start + 24*3600 {
-                    break;
+            let first = compared_events.partition_point(|e| e.start_unixtime < day_start);
+            let compared = compared_events[first..].iter().take_while(|e| day_range(e.start_unixtime)).collect::<Vec<_>>();
+            let mut compared_layout = overlap_layout(&compared.iter().map(|e| (e.start_unixtime, e.end_unixtime)).collect::<Vec<_>>());
+            if self.compare_mode == CompareMode::Lanes && !compared.is_empty() {
+                // Displayed schedule on the left half, compared one on the right half
+                for (_, column_count) in layout.iter_mut() {
+                    *column_count *= 2;
+                }
+                for (column, column_count) in compared_layout.iter_mut() {
+                    *column += *column_count;
+                    *column_count *= 2;
+                }
+            }
+            let mut layout = layout.into_iter();
+
+            let mut events = Vec::new();
+            for (e, column) in agenda_events.into_iter().zip(layout.by_ref()) {
+                let shared = match &e {
+                    AgendaEvent::Course(course) => compared.iter().any(|c| same_course(c, course)),
+                    AgendaEvent::Personal(_, _) => false,
+                };
+                events.push(html!{
+                    <EventComp
+                        week_day={d}
+                        event={e}
+                        day_start={day_start}
+                        agenda_link={ctx.link().clone()}
+                        comment_counts={Rc::clone(&ctx.props().comment_counts)}
+                        seen_comment_counts={Rc::clone(&ctx.props().seen_comment_counts)}
+                        column={column}
+                        shared={shared}>
+                    </EventComp>
+                });
+            }
+            for (e, column) in compared.iter().zip(compared_layout) {
+                let shared = ctx.props().events[first_course..].iter().take_while(|c| day_range(c.start_unixtime)).any(|c| same_course(c, e));
+                if shared && self.compare_mode == CompareMode::Overlay {
+                    continue;
                 }
                 events.push(html!{
                     <EventComp
                         week_day={d}
-                        event={e.clone()}
+                        event={AgendaEvent::Course((*e).clone())}
                         day_start={day_start}
                         agenda_link={ctx.link().clone()}
                         comment_counts={Rc::clone(&ctx.props().comment_counts)}
-                        seen_comment_counts={Rc::clone(&ctx.props().seen_comment_counts)}>
+                        seen_comment_counts={Rc::clone(&ctx.props().seen_comment_counts)}
+                        column={column}
+                        compared={true}
+                        shared={shared}>
                     </EventComp>
                 });
-                idx += 1;
+            }
+            if !compared.is_empty() {
+                // Highlight the gaps where both schedules are free
+                let busy_periods = |events: &[RawEvent]| events.iter().take_while(|e| e.start_unixtime < day_end).map(|e| (e.start_unixtime, e.end_unixtime)).collect::<Vec<_>>();
+                let schedules = [busy_periods(&ctx.props().events[first_course..]), busy_periods(&compared_events[first..])];
+                let bounds = (NaiveTime::from_hms_opt(8, 0, 0).unwrap(), NaiveTime::from_hms_opt(20, 0, 0).unwrap());
+                for (start, end) in find_free_slots(&schedules, current_day, current_day, bounds, 1800, true, user_tz()) {
+                    let top = 100.0 / 43200.0 * start.saturating_sub(day_start + 8 * 3600) as f64;
+                    let height = 100.0 / 43200.0 * (end - start) as f64;
+                    events.push(html!{ <div class="common-free-slot" style={format!("top: {top}%; height: {height}%;")}></div> });
+                }
+            }
+            for (e, column) in imported.into_iter().zip(layout) {
+                events.push(html!{
+                    <ImportedEventComp event={e.clone()} day_start={day_start} column={column} />
+                });
+            }
+
+            if let (true, Some(top)) = (current_day == now_local.date_naive(), now_indicator_top) {
+                events.push(html!{ <div class="now-indicator" style={format!("top: {top}%;")}></div> });
             }
 
             let mut day_style = String::new();
@@ -226,12 +480,13 @@ impl Component for Agenda {
             if mobile {
                 day_style.push_str(&format!("position: absolute; left: {}%;", (current_day.num_days_from_ce()-730000) * 20));
             } else {
+                day_style.push_str(&format!("width: calc(100% / {day_count} - 1%);"));
                 if selected_event_other_day {
                     day_style.push_str("opacity: 0; pointer-events: none;");
                     day_name_style.push_str("opacity: 0;");
                 }
                 if let Some((event, false, _)) = &ctx.props().popup {
-                    let week_day = Paris.from_local_datetime(&NaiveDateTime::from_timestamp_opt(event.start_unixtime as i64, 0).unwrap()).unwrap().weekday().num_days_from_monday();
+                    let week_day = days_from_week_start(user_tz().timestamp_opt(event.start_unixtime as i64, 0).unwrap().weekday());
                     day_name_style.push_str(&format!("transform: translateX(calc(-100%*{week_day} + -10px*{week_day}))"));
                 }
             }
@@ -242,7 +497,7 @@ impl Component for Agenda {
                 </span>
             });
             days.push(html! {
-                <div class="day" id={format!("day{d}")} style={day_style}>
+                <div class="day" id={format!("day{d}")} role="group" aria-label={format_date(current_day)} style={day_style} onclick={ctx.link().callback(move |event| AgendaMsg::CreatePersonalEvent { day_start, event })}>
                     { events }
                 </div>
             });
@@ -266,6 +521,29 @@ impl Component for Agenda {
                     user_info={Rc::clone(&ctx.props().user_info)} />
             }
         );
+        let refreshing = ctx.props().refreshing;
+        let syncing = ctx.props().syncing;
+        let (next_class_title, next_class_detail) = next_class_summary(&ctx.props().events, self.now as u64);
+        let time_zone_label = time_zone_indicator(self.now as u64);
+        let time_zone_differs = time_zone_label.is_some();
+        let time_zone_label = time_zone_label.unwrap_or_default();
+        let time_zone_title = tf("Heures affichées dans le fuseau {zone}, différent de celui du campus ({campus})", &[("zone", &user_tz().name()), ("campus", &CAMPUS_TIME_ZONE.name())]);
+        let opt_compare_button = ctx.props().compared_events.as_ref().map(|_| {
+            let label = t(match self.compare_mode {
+                CompareMode::Off => "Comparer",
+                CompareMode::Lanes => "Côte à côte",
+                CompareMode::Overlay => "Superposé",
+            });
+            html! {
+                <button class="friends-agenda-button" onclick={ctx.link().callback(|_| AgendaMsg::NextCompareMode)}>{label}</button>
+            }
+        });
+        let opt_editor = self.editing.as_ref().map(|event| {
+            let existing = ctx.props().personal_events.iter().any(|e| e.id == event.id && !e.deleted);
+            html! {
+                <PersonalEventEditor event={event.clone()} existing={existing} agenda_link={ctx.link().clone()} />
+            }
+        });
         let popup_container_style = match &ctx.props().popup {
             Some((_, false, popup_size)) => match mobile {
                 true => {
@@ -294,8 +572,8 @@ impl Component for Agenda {
             format!("right: {}%", 100 * (self.selected_day.num_days_from_ce() - 730000))
         } else if let Some((event, false, _)) = &ctx.props().popup {
             let c = self.counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-            let week_day = Paris.from_local_datetime(&NaiveDateTime::from_timestamp_opt(event.start_unixtime as i64, 0).unwrap()).unwrap().weekday().num_days_from_monday();
-            format!("right: calc((100%/6)*{week_day}); c: {};", c) // c is a workarround for a bug in Yew
+            let week_day = days_from_week_start(user_tz().timestamp_opt(event.start_unixtime as i64, 0).unwrap().weekday());
+            format!("right: calc((100%/{day_count})*{week_day}); c: {};", c) // c is a workarround for a bug in Yew
         } else {
             String::new()
         };
@@ -303,7 +581,27 @@ impl Component for Agenda {
             "src/agenda/agenda.html",
             onclick_previous = {ctx.link().callback(|_| AgendaMsg::Previous)},
             onclick_next = {ctx.link().callback(|_| AgendaMsg::Next)},
+            onkeydown_arrow = {Callback::from(click_on_enter)},
+            previous_day_label = {t("Jour précédent")},
+            next_day_label = {t("Jour suivant")},
             ...
         )
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlap_layout_columns() {
+        assert_eq!(overlap_layout(&[]), Vec::<(usize, usize)>::new());
+        assert_eq!(overlap_layout(&[(0, 10), (10, 20)]), vec![(0, 1), (0, 1)]);
+        assert_eq!(overlap_layout(&[(0, 10), (5, 15)]), vec![(0, 2), (1, 2)]);
+
+        // The third range reuses the column freed by the first one, and the group still has two columns
+        assert_eq!(overlap_layout(&[(0, 10), (5, 30), (12, 20)]), vec![(0, 2), (1, 2), (0, 2)]);
+
+        // Results are in input order, not in start order
+        assert_eq!(overlap_layout(&[(40, 50), (5, 15), (0, 10)]), vec![(0, 1), (1, 2), (0, 2)]);
+    }
+}
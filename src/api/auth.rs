@@ -1,6 +1,11 @@
 use super::*;
 
 pub(crate) async fn logout()-> Result<(), ApiError> {
+    // Send the changes that were not synced yet, before reading the counter of the logout request
+    if let Err(e) = SETTINGS_SYNC.push_pending().await {
+        e.handle_api_error();
+    }
+
     let window = window();
     let local_storage = window.local_storage().unwrap().unwrap();
     let (api_key, counter) = get_login_info();
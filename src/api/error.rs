@@ -16,7 +16,7 @@ impl std::fmt::Display for KnownApiError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let KnownApiError { kind, messages, message_en, message_fr, .. } = self;
         if let Some(messages) = messages {
-            let msg = messages.get(SETTINGS.locale()).unwrap_or(kind);
+            let msg = messages.get(SETTINGS.locale()).or_else(|| messages.get("en")).unwrap_or(kind);
             write!(f, "{msg} ({kind})")    
         } else if let (Some(msg_fr), Some(msg_en)) = (message_fr.as_ref(), message_en.as_ref()) {
             let msg = if SETTINGS.locale() == "fr" { msg_fr } else { msg_en };
@@ -171,4 +171,12 @@ impl ApiError{
             }
         }
     }
+
+    /// Whether the server doesn't have the requested endpoint, as servers that predate it
+    pub fn is_not_found(&self) -> bool {
+        match self {
+            ApiError::Unknown(value) => value.dyn_ref::<web_sys::Response>().map(|response| response.status() == 404).unwrap_or(false),
+            ApiError::Known(_) => false,
+        }
+    }
 }
@@ -27,41 +27,87 @@ pub async fn get_friends_schedule(uid: i64) -> Result<Vec<RawEvent>, ApiError> {
 }
 
 
-#[derive(Default, Clone)]
+/// Number of friends whose schedule is prefetched in the background
+const PREFETCHED_FRIENDS: usize = 3;
+
+/// A cached friend schedule
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+struct FriendsEventsEntry {
+    /// When the schedule was fetched
+    last_updated: u64,
+    /// When the schedule was last displayed
+    last_used: u64,
+    /// How many times the schedule was displayed
+    use_count: u32,
+    events: Rc<Vec<RawEvent>>,
+}
+
+/// A size-bounded cache of the schedules of friends, evicting the least recently used ones.
+#[derive(Default, Clone, PartialEq)]
 pub struct FriendsEvents {
-    events: HashMap<i64, (u64, Rc<Vec<RawEvent>>)>,
+    events: HashMap<i64, FriendsEventsEntry>,
+    /// Friends whose schedule is being fetched
+    refreshing: Rc<RefCell<Vec<i64>>>,
 }
 
 impl FriendsEvents {
     pub fn init() -> Self {
         let local_storage = window().local_storage().unwrap().unwrap();
+        let _ = local_storage.remove_item("cached_friends_events"); // Old format
     
-        let Ok(Some(cached_str)) = local_storage.get("cached_friends_events") else { return Self::default() };
-        let Ok(cached) = serde_json::from_str::<HashMap<i64, (u64, Vec<RawEvent>)>>(&cached_str) else { return Self::default() };
+        let Ok(Some(cached_str)) = local_storage.get("friends_events_cache") else { return Self::default() };
+        let Ok(events) = serde_json::from_str::<HashMap<i64, FriendsEventsEntry>>(&cached_str) else { return Self::default() };
+    
+        Self { events, refreshing: Rc::default() }
+    }
+
+    /// Maximum number of schedules to keep
+    pub fn capacity() -> usize {
+        let local_storage = window().local_storage().unwrap().unwrap();
+        local_storage.get("friends_cache_capacity").ok().flatten().and_then(|c| c.parse().ok()).unwrap_or(5)
+    }
+
+    pub fn set_capacity(capacity: usize) {
+        let local_storage = window().local_storage().unwrap().unwrap();
+        local_storage.set("friends_cache_capacity", &capacity.to_string()).unwrap();
+    }
+
+    /// Time after which a schedule is fetched again, in seconds
+    pub fn ttl() -> u64 {
+        let local_storage = window().local_storage().unwrap().unwrap();
+        local_storage.get("friends_cache_ttl").ok().flatten().and_then(|c| c.parse().ok()).unwrap_or(5*3600)
+    }
 
-        let mut event_map = HashMap::new();
-        for (uid, (time, events)) in cached {
-            event_map.insert(uid, (time, Rc::new(events)));
+    pub fn set_ttl(ttl: u64) {
+        let local_storage = window().local_storage().unwrap().unwrap();
+        local_storage.set("friends_cache_ttl", &ttl.to_string()).unwrap();
+    }
+
+    /// Drop the least recently used schedules until the cache fits its capacity
+    fn evict(&mut self) {
+        let capacity = Self::capacity();
+        while self.events.len() > capacity {
+            let Some(uid) = self.events.iter().min_by_key(|(_, entry)| entry.last_used.max(entry.last_updated)).map(|(uid, _)| *uid) else { break };
+            self.events.remove(&uid);
         }
-    
-        Self { events: event_map }
     }
 
     fn save(&self) {
-        // Sort by time, then take the 2 most recent
-        let mut records = self.events.iter().map(|(uid, (time, events))| (*uid, (*time, events.as_ref().to_owned()))).collect::<Vec<(i64, (u64, Vec<RawEvent>))>>();
-        records.sort_by_key(|(_, (time, _))| u64::MAX-time);
-        records.truncate(2);
-        
-        // Save to local storage
         let local_storage = window().local_storage().unwrap().unwrap();
-        let events = records.into_iter().map(|(id, (time, events))| (id, (time, events))).collect::<HashMap<i64, (u64, Vec<RawEvent>)>>();
-        local_storage.set("cached_friends_events", &serde_json::to_string(&events).unwrap()).unwrap();
+        local_storage.set("friends_events_cache", &serde_json::to_string(&self.events).unwrap()).unwrap();
     }
 
-    pub fn update_friend(uid: i64, app_link: AppLink) {
+    /// Fetch a schedule unless it is already being fetched
+    fn refresh(&self, uid: i64, app_link: AppLink) {
+        if self.refreshing.borrow().contains(&uid) {
+            return;
+        }
+        self.refreshing.borrow_mut().push(uid);
+        let refreshing = Rc::clone(&self.refreshing);
         spawn_local(async move {
-            match get_friends_schedule(uid).await {
+            let result = get_friends_schedule(uid).await;
+            refreshing.borrow_mut().retain(|u| *u != uid);
+            match result {
                 Ok(events) => app_link.send_message(AppMsg::FriendsEventsSuccess { uid, events }),
                 Err(err) => app_link.send_message(AppMsg::ApiFailure(err)),
             }
@@ -70,23 +116,58 @@ impl FriendsEvents {
 
     pub fn insert(&mut self, uid: i64, mut events: Vec<RawEvent>) {
         events.sort_by_key(|event| event.start_unixtime);
-        self.events.insert(uid, (now() as u64, Rc::new(events)));
+        let (last_used, use_count) = self.events.get(&uid).map(|entry| (entry.last_used, entry.use_count)).unwrap_or_default();
+        self.events.insert(uid, FriendsEventsEntry { last_updated: now() as u64, last_used, use_count, events: Rc::new(events) });
+        self.evict();
         self.save();
     }
 
-    pub fn get_events(&self, uid: i64, app_link: AppLink) -> Option<Rc<Vec<RawEvent>>> {
-        let res = self.events.get(&uid).map(|(last_updated, events)| (last_updated, Rc::clone(events)));
-        match res {
-            Some((last_updated, events)) => {
-                if now() - *last_updated as i64 > 5*3600 {
-                    Self::update_friend(uid, app_link);
-                }
-                Some(events)
-            },
-            None => {
-                Self::update_friend(uid, app_link);
-                None
+    /// Record that a schedule is being displayed so that it is kept and prefetched
+    pub fn record_use(&mut self, uid: i64) {
+        if let Some(entry) = self.events.get_mut(&uid) {
+            entry.last_used = now() as u64;
+            entry.use_count += 1;
+            self.save();
+        }
+    }
+
+    /// Forget the schedules of users that are no longer friends, and prefetch the most viewed ones
+    pub fn sync_with_friends(&mut self, friends: &FriendLists, app_link: AppLink) {
+        let len = self.events.len();
+        self.events.retain(|uid, _| friends.friends.iter().any(|(friend, _)| friend.uid == *uid));
+        if self.events.len() != len {
+            self.save();
+        }
+
+        let mut most_used = self.events.iter().map(|(uid, entry)| (*uid, entry.use_count)).collect::<Vec<_>>();
+        most_used.sort_by_key(|(_, use_count)| u32::MAX - use_count);
+        for (uid, _) in most_used.into_iter().take(PREFETCHED_FRIENDS) {
+            if self.is_stale(uid) {
+                self.refresh(uid, app_link.clone());
             }
         }
     }
+
+    /// Whether the cached events of a friend are missing or too old
+    pub fn is_stale(&self, uid: i64) -> bool {
+        self.events.get(&uid).map(|entry| now() - entry.last_updated as i64 > Self::ttl() as i64).unwrap_or(true)
+    }
+
+    /// Whether the schedule of a friend is being fetched
+    pub fn is_refreshing(&self, uid: i64) -> bool {
+        self.refreshing.borrow().contains(&uid)
+    }
+
+    /// Get the cached events of a friend without refreshing them
+    pub fn get_cached(&self, uid: i64) -> Option<Rc<Vec<RawEvent>>> {
+        self.events.get(&uid).map(|entry| Rc::clone(&entry.events))
+    }
+
+    /// Get the cached events of a friend, even if they are stale, and fetch them again in the background if needed
+    pub fn get_events(&self, uid: i64, app_link: AppLink) -> Option<Rc<Vec<RawEvent>>> {
+        if self.is_stale(uid) {
+            self.refresh(uid, app_link);
+        }
+        self.get_cached(uid)
+    }
 }
@@ -0,0 +1,37 @@
+use js_sys::encode_uri_component;
+
+use super::*;
+
+/// A choice of group the user makes, such as their language option, TP subgroup or sport slot
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct GroupDesc {
+    pub id: String,
+    pub name: String,
+    pub help: String,
+    pub values: Vec<String>,
+    /// Whether a value must be chosen
+    pub required: bool,
+}
+
+/// The chosen value of each group, by group id
+pub type GroupSelection = BTreeMap<String, String>;
+
+#[derive(Serialize, Deserialize)]
+pub struct GroupsResponse {
+    pub descriptors: Vec<GroupDesc>,
+    pub selection: GroupSelection,
+}
+
+pub async fn get_groups() -> Result<GroupsResponse, ApiError> {
+    api_get("groups").await
+}
+
+/// The schedule the user would have with other groups
+pub async fn preview_groups_schedule(selection: &GroupSelection) -> Result<Vec<RawEvent>, ApiError> {
+    let groups = serde_json::to_string(selection).unwrap();
+    api_get(format!("schedule?groups={}", encode_uri_component(&groups))).await
+}
+
+pub async fn set_groups(selection: &GroupSelection) -> Result<(), ApiError> {
+    api_post(selection, "groups").await
+}
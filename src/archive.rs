@@ -0,0 +1,111 @@
+//! Creation of ZIP archives ([specification](https://pkware.cachefly.net/webdocs/casestudies/APPNOTE.TXT)).
+//! Files are stored without compression, which every archive tool can read.
+
+use crate::prelude::*;
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = match crc & 1 {
+                1 => (crc >> 1) ^ 0xEDB8_8320,
+                _ => crc >> 1,
+            };
+        }
+    }
+    !crc
+}
+
+/// Date and time of the files, in the MS-DOS format used by ZIP archives
+fn dos_date_time(date_time: NaiveDateTime) -> (u16, u16) {
+    let time = (date_time.hour() << 11 | date_time.minute() << 5 | date_time.second() / 2) as u16;
+    let date = ((date_time.year().max(1980) - 1980) as u32) << 9 | date_time.month() << 5 | date_time.day();
+    (time, date as u16)
+}
+
+/// Build an archive containing the given files, named by their path in the archive
+pub fn zip(files: &[(String, Vec<u8>)], modified: NaiveDateTime) -> Vec<u8> {
+    // Bit 11 of the flags means that names are encoded in UTF-8
+    const UTF8_FLAG: u16 = 1 << 11;
+    let (time, date) = dos_date_time(modified);
+    let mut archive = Vec::new();
+    let mut central_directory = Vec::new();
+
+    for (name, data) in files {
+        let offset = archive.len() as u32;
+        let crc = crc32(data);
+        // Fields shared by the local header and the central directory, from the version needed to extract to the length of the extra field
+        let mut common = Vec::new();
+        common.extend_from_slice(&20u16.to_le_bytes());
+        common.extend_from_slice(&UTF8_FLAG.to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes()); // Stored
+        common.extend_from_slice(&time.to_le_bytes());
+        common.extend_from_slice(&date.to_le_bytes());
+        common.extend_from_slice(&crc.to_le_bytes());
+        common.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        common.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        common.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes());
+
+        archive.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        archive.extend_from_slice(&common);
+        archive.extend_from_slice(name.as_bytes());
+        archive.extend_from_slice(data);
+
+        central_directory.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        central_directory.extend_from_slice(&20u16.to_le_bytes()); // Version made by
+        central_directory.extend_from_slice(&common);
+        central_directory.extend_from_slice(&[0; 10]); // Comment length, disk number and attributes
+        central_directory.extend_from_slice(&offset.to_le_bytes());
+        central_directory.extend_from_slice(name.as_bytes());
+    }
+
+    let central_directory_offset = archive.len() as u32;
+    archive.extend_from_slice(&central_directory);
+    archive.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+    archive.extend_from_slice(&[0; 4]); // Disk numbers
+    archive.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    archive.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    archive.extend_from_slice(&(central_directory.len() as u32).to_le_bytes());
+    archive.extend_from_slice(&central_directory_offset.to_le_bytes());
+    archive.extend_from_slice(&0u16.to_le_bytes()); // Comment length
+    archive
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b"The quick brown fox jumps over the lazy dog"), 0x414F_A339);
+    }
+
+    #[test]
+    fn dos_date_time_fields() {
+        let date_time = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap().and_hms_opt(13, 45, 31).unwrap();
+        assert_eq!(dos_date_time(date_time), (13 << 11 | 45 << 5 | 15, 44 << 9 | 3 << 5 | 15));
+    }
+
+    #[test]
+    fn archive_layout() {
+        let modified = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap();
+        let files = vec![(String::from("a.txt"), b"hello".to_vec()), (String::from("dir/b.json"), b"{}".to_vec())];
+        let archive = zip(&files, modified);
+
+        assert_eq!(archive[..4], [0x50, 0x4b, 0x03, 0x04]);
+        assert_eq!(archive[14..18], crc32(b"hello").to_le_bytes());
+        assert_eq!(&archive[30..35], b"a.txt");
+        assert_eq!(&archive[35..40], b"hello");
+
+        // The end of central directory record gives the number of files and where the central directory is
+        let end = &archive[archive.len() - 22..];
+        assert_eq!(end[..4], [0x50, 0x4b, 0x05, 0x06]);
+        assert_eq!(u16::from_le_bytes([end[10], end[11]]), 2);
+        let offset = u32::from_le_bytes([end[16], end[17], end[18], end[19]]) as usize;
+        assert_eq!(archive[offset..offset + 4], [0x50, 0x4b, 0x01, 0x02]);
+    }
+}
@@ -0,0 +1,51 @@
+//! Badge on the app icon counting what the user hasn't seen yet, mirrored in the document title.
+//!
+//! The badge is set with the [Badging API](https://developer.mozilla.org/en-US/docs/Web/API/Badging_API), which is only available to the installed app on some browsers.
+
+use std::collections::HashSet;
+use crate::prelude::*;
+
+/// Number of unread notifications, pending friend requests and unseen comments on my events
+pub fn badge_count(notifications: &LocalNotificationTracker, friends: &Option<FriendLists>, events: &[RawEvent], comment_counts: &CommentCounts, seen_comment_counts: &CommentCounts) -> usize {
+    let unread_notifications = notifications.unread_count();
+    let friend_requests = friends.as_ref().map(|f| f.incoming.len()).unwrap_or(0);
+    if comment_counts.is_empty() {
+        return unread_notifications + friend_requests;
+    }
+    let eids = events.iter().map(|e| e.eid.as_str()).collect::<HashSet<_>>();
+    let unseen_comments: usize = comment_counts.iter()
+        .filter(|(eid, _)| eids.contains(eid.as_str()))
+        .map(|(eid, count)| count.saturating_sub(seen_comment_counts.get(eid).copied().unwrap_or(0)))
+        .sum();
+    unread_notifications + friend_requests + unseen_comments
+}
+
+/// Call a method of the navigator if the browser supports it
+fn call_navigator(method: &str, args: &Array) -> Result<(), JsValue> {
+    let navigator = window().navigator();
+    let function = Reflect::get(&navigator, &method.into())?;
+    let Some(function) = function.dyn_ref::<Function>() else { return Ok(()) };
+    let promise: js_sys::Promise = Reflect::apply(function, &navigator, args)?.dyn_into()?;
+    // The promise is rejected when the app isn't installed, which doesn't matter
+    spawn_local(async move {
+        let _ = JsFuture::from(promise).await;
+    });
+    Ok(())
+}
+
+/// Show the count on the app icon and in front of the page title, or clear them if it is zero
+pub fn set_badge(count: usize, title: &str) {
+    let result = match count {
+        0 => call_navigator("clearAppBadge", &Array::new()),
+        count => call_navigator("setAppBadge", &Array::of1(&(count as f64).into())),
+    };
+    if let Err(e) = result {
+        log!("Failed to set app badge: {e:?}");
+    }
+
+    let document = window().doc();
+    match count {
+        0 => document.set_title(title),
+        count => document.set_title(&format!("({count}) {title}")),
+    }
+}
@@ -18,11 +18,15 @@ pub enum Msg {
     Next,
     Previous,
     Goto { day: u32, month: u32, year: i32 },
+    /// Keyboard navigation between the days of the grid
+    Key(KeyboardEvent),
     TriggerFold,
 }
 
 pub struct Calendar {
     folded: bool,
+    /// Whether the selected day should get the focus once rendered, after a keyboard navigation
+    focus_selected: bool,
     on_click: Closure<dyn FnMut(web_sys::MouseEvent)>,
 }
 
@@ -48,6 +52,7 @@ impl Component for Calendar {
         }) as Box<dyn FnMut(_)>);
         Calendar {
             folded: true,
+            focus_selected: false,
             on_click,
         }
     }
@@ -96,6 +101,27 @@ impl Component for Calendar {
             Msg::Goto { day, month, year } => {
                 ctx.props().agenda_link.send_message(AgendaMsg::Goto {day, month, year});
             },
+            Msg::Key(event) => {
+                let selected = NaiveDate::from_ymd_opt(ctx.props().year, ctx.props().month, ctx.props().day).unwrap();
+                let date = match event.key().as_str() {
+                    "ArrowLeft" => selected.pred_opt(),
+                    "ArrowRight" => selected.succ_opt(),
+                    "ArrowUp" => selected.checked_sub_signed(chrono::Duration::days(7)),
+                    "ArrowDown" => selected.checked_add_signed(chrono::Duration::days(7)),
+                    "PageUp" => selected.checked_sub_months(chrono::Months::new(1)),
+                    "PageDown" => selected.checked_add_months(chrono::Months::new(1)),
+                    "Escape" => {
+                        ctx.link().send_message(Msg::TriggerFold);
+                        return false;
+                    }
+                    _ => return false,
+                };
+                event.prevent_default();
+                let Some(date) = date else { return false };
+                self.focus_selected = true;
+                ctx.props().agenda_link.send_message(AgendaMsg::Goto { day: date.day(), month: date.month(), year: date.year() });
+                return false;
+            }
             Msg::TriggerFold => {
                 self.folded = !self.folded;
                 match self.folded {
@@ -107,44 +133,51 @@ impl Component for Calendar {
         true
     }
 
+    fn rendered(&mut self, _ctx: &Context<Self>, _first_render: bool) {
+        if std::mem::take(&mut self.focus_selected) {
+            if let Some(selected) = window().doc().get_element_by_id("calendar-case-selected").and_then(|el| el.dyn_into::<HtmlElement>().ok()) {
+                let _ = selected.focus();
+            }
+        }
+    }
+
     fn view(&self, ctx: &Context<Self>) -> Html {
-        let display_month = format!("{} {}", t(match ctx.props().month {
-            1 => "Janvier",
-            2 => "Février",
-            3 => "Mars",
-            4 => "Avril",
-            5 => "Mai",
-            6 => "Juin",
-            7 => "Juillet",
-            8 => "Août",
-            9 => "Septembre",
-            10 => "Octobre",
-            11 => "Novembre",
-            12 => "Décembre",
-            _ => unreachable!(),
-        }), ctx.props().year);
+        let display_month = format!("{} {}", month_name(ctx.props().month), ctx.props().year);
 
         let first_day = NaiveDate::from_ymd_opt(ctx.props().year, ctx.props().month, 1).unwrap();
         let last_day = NaiveDate::from_ymd_opt(ctx.props().year, (ctx.props().month % 12) + 1, 1).unwrap().pred_opt().unwrap();
-        let today = Local::now().date_naive();
+        let today = Local::now().with_timezone(&user_tz()).date_naive();
 
         let mut calendar_cases = Vec::new();
-        for _ in 0..first_day.weekday().number_from_monday() - 1 {
+        for _ in 0..days_from_week_start(first_day.weekday()) {
             calendar_cases.push(html! {
-                <span class="calendar-case" onclick={ctx.link().callback(|_| Msg::Previous)}></span>
+                <span class="calendar-case" role="gridcell" onclick={ctx.link().callback(|_| Msg::Previous)}></span>
             });
         }
         for day in 1..=last_day.day() {
             let (month, year) = (ctx.props().month, ctx.props().year);
             let date = NaiveDate::from_ymd_opt(year, month, day).unwrap();
             let id = if day==ctx.props().day {Some("calendar-case-selected")} else if date==today {Some("calendar-case-today")} else {None};
+            let selected = day == ctx.props().day;
+            // Only the selected day can be reached with Tab, the others are reached with the arrow keys
             calendar_cases.push(html! {
-                <span class="calendar-case" id={id} onclick={ctx.link().callback(move |_| Msg::Goto {day,month,year})}>{day.to_string()}</span>
+                <span
+                    class="calendar-case"
+                    id={id}
+                    role="gridcell"
+                    tabindex={if selected {"0"} else {"-1"}}
+                    aria-selected={selected.to_string()}
+                    aria-current={(date == today).then_some("date")}
+                    aria-label={format_date(date)}
+                    onclick={ctx.link().callback(move |_| Msg::Goto {day,month,year})}
+                    onkeydown={Callback::from(click_on_enter)}>
+                    {day.to_string()}
+                </span>
             });
         }
         while calendar_cases.len() % 7 != 0 {
             calendar_cases.push(html! {
-                <span class="calendar-case" onclick={ctx.link().callback(|_| Msg::Next)}></span>
+                <span class="calendar-case" role="gridcell" onclick={ctx.link().callback(|_| Msg::Next)}></span>
             });
         }
 
@@ -163,9 +196,17 @@ impl Component for Calendar {
             onclick_previous = {ctx.link().callback(|_| Msg::Previous)},
             onclick_fold = {ctx.link().callback(|_| Msg::TriggerFold)},
             onclick_next = {ctx.link().callback(|_| Msg::Next)},
+            day_name_iter = {week_days().map(short_weekday_name)},
+            day_label_iter = {week_days().map(weekday_name)},
+            onkeydown_grid = {ctx.link().callback(Msg::Key)},
+            onkeydown_fold = {Callback::from(click_on_enter)},
+            previous_label = {t("Précédent")},
+            next_label = {t("Suivant")},
             week_iter = {week_iter.into_iter()},
             cases_iter = {cases_iter.into_iter()},
             is_folded = {self.folded},
+            expanded = {(!self.folded).to_string()},
+            month_label = {display_month.clone()},
             ...
         }
     }
@@ -42,7 +42,7 @@ impl Component for Checkbox {
         html! {
             <div class="checkbox">
                 <input type="checkbox" id={fid.clone()} checked={ctx.props().checked} onchange={ctx.link().callback(CheckboxMsg::Change)} />
-                <label for={fid.clone()} class="checkbox-box"></label>
+                <label for={fid.clone()} class="checkbox-box" aria-hidden="true"></label>
                 <label for={fid.clone()}>{ctx.props().message.as_str()}</label>
             </div>
         }
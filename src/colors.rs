@@ -6,6 +6,8 @@ lazy_static::lazy_static!{
     pub static ref COLORS_CHANGED: AtomicBool = AtomicBool::new(false);
 }
 
+/// Colors of the courses, synced with the server by [SETTINGS_SYNC].
+/// Changes are also published to the colors endpoint, for servers and devices that don't sync settings.
 pub struct Colors {
     local_colors: Arc<Mutex<HashMap<String, String>>>,
     to_publish: Arc<Mutex<Vec<(String, String)>>>,
@@ -35,17 +37,32 @@ impl Colors {
         }
     }
 
+    /// Courses that have a color
+    pub fn courses(&self) -> Vec<String> {
+        match self.local_colors.try_lock() {
+            Ok(v) => v.keys().cloned().collect(),
+            Err(_) => {sentry_report("try lock impossible"); Vec::new()},
+        }
+    }
+
     pub fn set(&self, course: &str, background_color: String) {
+        match self.to_publish.as_ref().try_lock() {
+            Ok(mut v) => v.push((course.to_string(), background_color.clone())),
+            Err(_) => sentry_report("try lock impossible"),
+        }
+        self.set_synced(course, background_color);
+        SETTINGS_SYNC.record(format!("color:{course}"));
+    }
+
+    /// Set a color without syncing the change with the server
+    pub fn set_synced(&self, course: &str, background_color: String) {
         match self.local_colors.try_lock(){
             Ok(mut v) => {
-                v.insert(course.to_string(), background_color.clone());
+                v.insert(course.to_string(), background_color);
             },
             Err(_) => sentry_report("try lock impossible"),
         }
-        match self.to_publish.as_ref().try_lock() {
-            Ok(mut v) => v.push((course.to_string(), background_color)),
-            Err(_) => sentry_report("try lock impossible"),
-        } 
+        crate::colors::COLORS_CHANGED.store(true, std::sync::atomic::Ordering::Relaxed);
         self.save();
     }
 
@@ -72,8 +89,9 @@ impl Colors {
         });
     }
 
+    /// Add the colors saved by the colors endpoint, which predates the sync of settings.
+    /// They only apply to the courses that have no color yet, as synced colors are more recent.
     pub fn update_colors(&self, remote_colors: HashMap<String, String>) {
-        // Merge new colors
         let mut local_colors = match self.local_colors.try_lock() {
             Ok(v) => v,
             Err(_) => {sentry_report("try lock impossible"); return},
@@ -87,7 +105,10 @@ impl Colors {
                 to_publish.push((course.to_string(), color.to_string()));
             }
         }
-        local_colors.extend(remote_colors);
+        drop(to_publish);
+        for (course, color) in remote_colors {
+            local_colors.entry(course).or_insert(color);
+        }
         // Save last updated time
 
         let local_storage = window().local_storage().unwrap().unwrap();
@@ -116,3 +137,120 @@ impl Colors {
         });
     }
 }
+
+/// Parse a color in the `#rrggbb` format of color inputs
+fn parse_hex_color(color: &str) -> Option<[u8; 3]> {
+    let hex = color.trim().strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let component = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some([component(0)?, component(2)?, component(4)?])
+}
+
+/// Relative luminance of a color, as defined by the [WCAG](https://www.w3.org/TR/WCAG21/#dfn-relative-luminance)
+fn relative_luminance(color: [u8; 3]) -> f64 {
+    let [r, g, b] = color.map(|c| {
+        let c = c as f64 / 255.0;
+        match c <= 0.03928 {
+            true => c / 12.92,
+            false => ((c + 0.055) / 1.055).powf(2.4),
+        }
+    });
+    0.2126 * r + 0.7152 * g + 0.0722 * b
+}
+
+/// Contrast ratio between two colors, from 1 to 21
+fn contrast_ratio(a: [u8; 3], b: [u8; 3]) -> f64 {
+    let (a, b) = (relative_luminance(a), relative_luminance(b));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+/// Theme and contrast attributes of the document, with the background of the days they give
+type DayBackground = ((Option<String>, Option<String>), [u8; 3]);
+
+lazy_static::lazy_static!{
+    static ref DAY_BACKGROUND: Mutex<Option<DayBackground>> = Mutex::new(None);
+}
+
+/// Background color of the days, on which events are drawn.
+/// The computed style is only read again when the theme or contrast changes.
+fn day_background() -> [u8; 3] {
+    let html = window().doc().first_element_child().unwrap();
+    let attributes = (html.get_attribute("data-theme"), html.get_attribute("data-contrast"));
+    let mut cached = DAY_BACKGROUND.lock().unwrap();
+    if let Some((cached_attributes, day)) = cached.as_ref() {
+        if *cached_attributes == attributes {
+            return *day;
+        }
+    }
+
+    let day = window().get_computed_style(&html).ok().flatten().and_then(|style| style.get_property_value("--day").ok());
+    let day = match day.as_deref().and_then(parse_hex_color) {
+        Some(day) => day,
+        None if attributes.0.as_deref() == Some("dark") => [0, 0, 0],
+        None => [255, 255, 255],
+    };
+    *cached = Some((attributes, day));
+    day
+}
+
+/// Background of an event of the given color.
+/// The color is half transparent, except in high contrast mode.
+pub fn event_background(color: &str) -> String {
+    match high_contrast() {
+        true => color.to_string(),
+        false => format!("{color}80"),
+    }
+}
+
+/// Black or white, whichever contrasts the most with the background of an event of the given color
+pub fn event_text_color(color: &str) -> &'static str {
+    let Some(mut background) = parse_hex_color(color) else { return "var(--text)" };
+    if !high_contrast() {
+        // The background is blended with the day by half
+        let day = day_background();
+        for (c, d) in background.iter_mut().zip(day) {
+            *c = ((*c as u16 + d as u16) / 2) as u8;
+        }
+    }
+    text_color_on(background)
+}
+
+/// Black or white, whichever contrasts the most with an opaque background
+fn text_color_on(background: [u8; 3]) -> &'static str {
+    match contrast_ratio(background, [0, 0, 0]) >= contrast_ratio(background, [255, 255, 255]) {
+        true => "black",
+        false => "white",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contrast_ratios() {
+        assert!((contrast_ratio([0, 0, 0], [255, 255, 255]) - 21.0).abs() < 1e-9);
+        assert!((contrast_ratio([255, 255, 255], [0, 0, 0]) - 21.0).abs() < 1e-9);
+        assert!((contrast_ratio([203, 108, 230], [203, 108, 230]) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn hex_colors() {
+        assert_eq!(parse_hex_color("#CB6CE6"), Some([203, 108, 230]));
+        assert_eq!(parse_hex_color(" #000000 "), Some([0, 0, 0]));
+        assert_eq!(parse_hex_color("#abc"), None);
+        assert_eq!(parse_hex_color("#gggggg"), None);
+        assert_eq!(parse_hex_color("CB6CE6"), None);
+        assert_eq!(parse_hex_color("#éééé"), None);
+    }
+
+    #[test]
+    fn text_colors() {
+        assert_eq!(text_color_on([255, 255, 255]), "black");
+        assert_eq!(text_color_on([255, 230, 100]), "black");
+        assert_eq!(text_color_on([0, 0, 0]), "white");
+        assert_eq!(text_color_on([20, 40, 120]), "white");
+    }
+}
@@ -0,0 +1,194 @@
+//! Formatting of dates and times in the language of the user.
+//!
+//! The 12-hour clock and the first day of the week are preferences of the settings page.
+//! When left on automatic, they come from the `X-Hour-Cycle` and `X-First-Weekday` headers of the catalog of the language.
+//!
+//! Dates are displayed, and events split into days, in the time zone chosen in the settings, which is the one of the campus by default.
+
+use crate::prelude::*;
+
+/// Time zone of the campus, in which the schedule is published
+pub const CAMPUS_TIME_ZONE: Tz = Paris;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TimeZoneSetting {
+    /// Follow the time zone of the browser
+    Auto,
+    Fixed(Tz),
+}
+
+lazy_static! {
+    static ref BROWSER_TIME_ZONE: Option<Tz> = {
+        let options = js_sys::Intl::DateTimeFormat::new(&Array::new(), &js_sys::Object::new()).resolved_options();
+        let time_zone = Reflect::get(&options, &"timeZone".into()).ok()?.as_string()?;
+        time_zone.parse().ok()
+    };
+}
+
+pub fn browser_time_zone() -> Option<Tz> {
+    *BROWSER_TIME_ZONE
+}
+
+/// The time zone in which dates are displayed
+pub fn user_tz() -> Tz {
+    match SETTINGS.time_zone.get() {
+        TimeZoneSetting::Auto => browser_time_zone().unwrap_or(CAMPUS_TIME_ZONE),
+        TimeZoneSetting::Fixed(tz) => tz,
+    }
+}
+
+/// Abbreviation of the time zone at `timestamp`, such as "EST", when times are displayed in another time zone than the campus one
+pub fn time_zone_indicator(timestamp: u64) -> Option<String> {
+    let tz = user_tz();
+    if tz == CAMPUS_TIME_ZONE {
+        return None;
+    }
+    Some(tz.timestamp_opt(timestamp as i64, 0).unwrap().format("%Z").to_string())
+}
+
+/// Timestamp of the start of a day in the time zone of the user.
+/// In time zones that change to summer time at midnight, the day starts at the end of the gap.
+pub fn day_start(date: NaiveDate) -> u64 {
+    let tz = user_tz();
+    (0..24).find_map(|hour| tz.from_local_datetime(&date.and_hms_opt(hour, 0, 0).unwrap()).earliest())
+        .map(|start| start.timestamp() as u64)
+        .unwrap_or_default()
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum HourCycle {
+    Auto = 0,
+    H24,
+    H12,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum WeekStart {
+    Auto = 0,
+    Monday,
+    Sunday,
+}
+
+/// Whether times are displayed with the 12-hour clock
+pub fn uses_12_hours() -> bool {
+    match SETTINGS.hour_cycle.get() {
+        HourCycle::Auto => locale_header("X-Hour-Cycle") == Some("h12"),
+        HourCycle::H24 => false,
+        HourCycle::H12 => true,
+    }
+}
+
+pub fn first_weekday() -> Weekday {
+    match SETTINGS.week_start.get() {
+        WeekStart::Auto if locale_header("X-First-Weekday") == Some("sunday") => Weekday::Sun,
+        WeekStart::Auto | WeekStart::Monday => Weekday::Mon,
+        WeekStart::Sunday => Weekday::Sun,
+    }
+}
+
+pub fn weekday_name(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => t("Lundi"),
+        Weekday::Tue => t("Mardi"),
+        Weekday::Wed => t("Mercredi"),
+        Weekday::Thu => t("Jeudi"),
+        Weekday::Fri => t("Vendredi"),
+        Weekday::Sat => t("Samedi"),
+        Weekday::Sun => t("Dimanche"),
+    }
+}
+
+pub fn short_weekday_name(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => t("Lun"),
+        Weekday::Tue => t("Mar"),
+        Weekday::Wed => t("Mer"),
+        Weekday::Thu => t("Jeu"),
+        Weekday::Fri => t("Ven"),
+        Weekday::Sat => t("Sam"),
+        Weekday::Sun => t("Dim"),
+    }
+}
+
+pub fn month_name(month: u32) -> &'static str {
+    match month {
+        1 => t("Janvier"),
+        2 => t("Février"),
+        3 => t("Mars"),
+        4 => t("Avril"),
+        5 => t("Mai"),
+        6 => t("Juin"),
+        7 => t("Juillet"),
+        8 => t("Août"),
+        9 => t("Septembre"),
+        10 => t("Octobre"),
+        11 => t("Novembre"),
+        12 => t("Décembre"),
+        _ => unreachable!(),
+    }
+}
+
+/// The seven days of the week, starting with the first day of the week of the user
+pub fn week_days() -> impl Iterator<Item = Weekday> {
+    std::iter::successors(Some(first_weekday()), |day| Some(day.succ())).take(7)
+}
+
+/// Number of days between the first day of the week and `day`
+pub fn days_from_week_start(day: Weekday) -> u32 {
+    (day.num_days_from_monday() + 7 - first_weekday().num_days_from_monday()) % 7
+}
+
+pub fn format_time(time: impl Timelike) -> String {
+    let time = NaiveTime::from_hms_opt(time.hour(), time.minute(), 0).unwrap();
+    match uses_12_hours() {
+        true => time.format(t("%-I:%M %p")).to_string(),
+        false => time.format(t("%Hh%M")).to_string(),
+    }
+}
+
+/// Weekday and day of the month, such as "Monday 1st"
+pub fn format_day(day_name: Weekday, day: u32) -> String {
+    tord("{day_name} {n}", day as u64, &[("day_name", &weekday_name(day_name))])
+}
+
+/// Full date without the year, such as "Monday 1st March"
+pub fn format_date(date: NaiveDate) -> String {
+    tord("{day_name} {n} {month}", date.day() as u64, &[("day_name", &weekday_name(date.weekday())), ("month", &month_name(date.month()))])
+}
+
+/// Date relative to `today` when it is close, such as "tomorrow" or "next Tuesday", or the full date otherwise
+pub fn format_relative_day(date: NaiveDate, today: NaiveDate) -> String {
+    match (date - today).num_days() {
+        -1 => t("hier").to_string(),
+        0 => t("aujourd'hui").to_string(),
+        1 => t("demain").to_string(),
+        2..=6 => tf("{day_name} prochain", &[("day_name", &weekday_name(date.weekday()))]),
+        _ => format_date(date),
+    }
+}
+
+/// Date relative to today followed by the time, such as "tomorrow at 8:00 AM"
+pub fn format_relative_date_time(datetime: DateTime<Tz>, now: DateTime<Tz>) -> String {
+    let day = format_relative_day(datetime.date_naive(), now.date_naive());
+    tf("{day} à {time}", &[("day", &day), ("time", &format_time(datetime))])
+}
+
+/// Time elapsed since something happened, such as "3 minutes ago"
+pub fn format_time_diff(diff: i64) -> String {
+    let diff = diff.max(0) as u64;
+    if diff < 60 {
+        tn("il y a {n} seconde", "il y a {n} secondes", diff, &[])
+    } else if diff < 3600 {
+        tn("il y a {n} minute", "il y a {n} minutes", diff / 60, &[])
+    } else if diff < 86400 {
+        tn("il y a {n} heure", "il y a {n} heures", diff / 3600, &[])
+    } else if diff < 7*86400 {
+        tn("il y a {n} jour", "il y a {n} jours", diff / 86400, &[])
+    } else if diff < 30*86400 {
+        tn("il y a {n} semaine", "il y a {n} semaines", diff / (7*86400), &[])
+    } else if diff < 365*86400 {
+        tn("il y a {n} mois", "il y a {n} mois", diff / (30*86400), &[])
+    } else {
+        tn("il y a {n} an", "il y a {n} ans", diff / (365*86400), &[])
+    }
+}
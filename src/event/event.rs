@@ -4,14 +4,54 @@ lazy_static::lazy_static!{
     static ref ID_COUNTER: AtomicUsize = AtomicUsize::new(0);
 }
 
+/// Anything that can be displayed as an event in the agenda
+#[derive(Clone, PartialEq)]
+pub enum AgendaEvent {
+    Course(RawEvent),
+    /// An occurrence of a personal event, starting at the given time
+    Personal(PersonalEvent, u64),
+}
+
+impl AgendaEvent {
+    pub fn eid(&self) -> Option<&str> {
+        match self {
+            AgendaEvent::Course(event) => Some(&event.eid),
+            AgendaEvent::Personal(_, _) => None,
+        }
+    }
+
+    pub fn start_unixtime(&self) -> u64 {
+        match self {
+            AgendaEvent::Course(event) => event.start_unixtime,
+            AgendaEvent::Personal(_, start) => *start,
+        }
+    }
+
+    pub fn end_unixtime(&self) -> u64 {
+        match self {
+            AgendaEvent::Course(event) => event.end_unixtime,
+            AgendaEvent::Personal(event, start) => start + event.duration(),
+        }
+    }
+}
+
 #[derive(Properties, Clone)]
 pub struct EventCompProps {
-    pub event: RawEvent,
+    pub event: AgendaEvent,
     pub day_start: u64,
     pub agenda_link: AgendaLink,
     pub week_day: u8,
     pub comment_counts: Rc<CommentCounts>,
     pub seen_comment_counts: Rc<CommentCounts>,
+    /// Column of the event and number of columns, when it overlaps other events
+    #[prop_or((0, 1))]
+    pub column: (usize, usize),
+    /// Whether the event belongs to the schedule displayed for comparison
+    #[prop_or_default]
+    pub compared: bool,
+    /// Whether the event is shared by both compared schedules
+    #[prop_or_default]
+    pub shared: bool,
 }
 
 impl PartialEq for EventCompProps {
@@ -20,8 +60,11 @@ impl PartialEq for EventCompProps {
             && self.event == other.event
             && self.day_start == other.day_start
             && self.week_day == other.week_day
-            && self.comment_counts.get(&self.event.eid) == other.comment_counts.get(&other.event.eid)
-            && self.seen_comment_counts.get(&self.event.eid) == other.seen_comment_counts.get(&other.event.eid)
+            && self.column == other.column
+            && self.compared == other.compared
+            && self.shared == other.shared
+            && self.event.eid().and_then(|eid| self.comment_counts.get(eid)) == other.event.eid().and_then(|eid| other.comment_counts.get(eid))
+            && self.event.eid().and_then(|eid| self.seen_comment_counts.get(eid)) == other.event.eid().and_then(|eid| other.seen_comment_counts.get(eid))
     }
 }
 
@@ -59,9 +102,10 @@ impl HackTraitEventFormat for RawEvent {
     }
 
     fn format_time(&self) -> String {
-        let start = Paris.timestamp_opt(self.start_unixtime as i64, 0).unwrap();
-        let end = Paris.timestamp_opt(self.end_unixtime as i64, 0).unwrap();
-        format!("{} - {}", start.time().format("%Hh%M"), end.time().format("%Hh%M"))
+        let start = user_tz().timestamp_opt(self.start_unixtime as i64, 0).unwrap();
+        let end = user_tz().timestamp_opt(self.end_unixtime as i64, 0).unwrap();
+        let time_zone = time_zone_indicator(self.start_unixtime).map(|tz| format!(" ({tz})")).unwrap_or_default();
+        format!("{} - {}{time_zone}", format_time(start), format_time(end))
     }
 }
 
@@ -74,34 +118,67 @@ impl Component for EventComp {
     fn create(_ctx: &Context<Self>) -> Self { Self {} }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
-        let name = ctx.props().event.format_name();
-        let location = ctx.props().event.format_location();
-        let bg_color = COLORS.get(&ctx.props().event.summary);
+        let event = &ctx.props().event;
+        let (name, teachers, location, bg_color, onclick) = match event {
+            AgendaEvent::Course(event) => {
+                let eid = event.eid.clone(); // FIXME: what if eid contains slashes and stuff?
+                let onclick = ctx.props().agenda_link.callback(move |_| AgendaMsg::AppMsg(AppMsg::SetPage(Page::Event { eid: eid.clone() } )));
+                (event.format_name(), event.teachers.join(", "), event.format_location(), COLORS.get(&event.summary), onclick)
+            }
+            AgendaEvent::Personal(event, _) => {
+                let id = event.id.clone();
+                let onclick = ctx.props().agenda_link.callback(move |_| AgendaMsg::EditPersonalEvent(id.clone()));
+                let notes = event.notes.lines().next().unwrap_or_default().to_string();
+                (event.title.clone(), notes, None, event.color.clone(), onclick)
+            }
+        };
+        let mut event_classes = String::from(match event {
+            AgendaEvent::Course(_) => "event",
+            AgendaEvent::Personal(_, _) => "event personal-event",
+        });
+        if ctx.props().compared { event_classes.push_str(" compared-event"); }
+        if ctx.props().shared { event_classes.push_str(" shared-event"); }
 
         // Calculate position
         let day_sec_count = 43200.0;
-        let sec_offset = ctx.props().event.start_unixtime.saturating_sub(ctx.props().day_start + 8 * 3600);
+        let sec_offset = event.start_unixtime().saturating_sub(ctx.props().day_start + 8 * 3600);
         let percent_offset = 100.0 / day_sec_count * sec_offset as f64;
-        if ctx.props().event.start_unixtime >= ctx.props().event.end_unixtime {
+        if event.start_unixtime() >= event.end_unixtime() {
             log!("Event {} in {:?}  ends before it starts", name, location);
             return html!{};
         }
-        let percent_height = 100.0 / day_sec_count * (ctx.props().event.end_unixtime - ctx.props().event.start_unixtime) as f64;
+        let percent_height = 100.0 / day_sec_count * (event.end_unixtime() - event.start_unixtime()) as f64;
+        let (column, column_count) = ctx.props().column;
+        let percent_left = 100.0 * column as f64 / column_count as f64;
+        let percent_width = 100.0 / column_count as f64;
 
         // Count comments
-        let opt_comment_count = ctx.props().comment_counts.get(&ctx.props().event.eid).copied();
-        let seen_comment_count = ctx.props().seen_comment_counts.get(&ctx.props().event.eid).copied().unwrap_or_default();
-        let comment_count = ctx.props().comment_counts.get(&ctx.props().event.eid).copied().unwrap_or_default();
+        let opt_comment_count = event.eid().and_then(|eid| ctx.props().comment_counts.get(eid)).copied();
+        let seen_comment_count = event.eid().and_then(|eid| ctx.props().seen_comment_counts.get(eid)).copied().unwrap_or_default();
+        let comment_count = opt_comment_count.unwrap_or_default();
         let seen = seen_comment_count >= comment_count;
 
+        // Everything the event shows, read by screen readers
+        let start = user_tz().timestamp_opt(event.start_unixtime() as i64, 0).unwrap();
+        let end = user_tz().timestamp_opt(event.end_unixtime() as i64, 0).unwrap();
+        let mut label = vec![name.clone(), format!("{} - {}", format_time(start), format_time(end))];
+        label.extend(location.clone());
+        if !teachers.is_empty() {
+            label.push(teachers.clone());
+        }
+        if let Some(count) = opt_comment_count {
+            label.push(tn("{n} commentaire", "{n} commentaires", count as u64, &[]));
+        }
+        let aria_label = label.join(", ");
+
         // Render
-        let eid = ctx.props().event.eid.clone(); // FIXME: what if eid contains slashes and stuff?
-        let onclick = ctx.props().agenda_link.callback(move |_| AgendaMsg::AppMsg(AppMsg::SetPage(Page::Event { eid: eid.clone() } )));
         template_html!(
             "src/event/event.html",
-            teachers = { ctx.props().event.teachers.join(", ")},
             opt_location = location,
             bg_color = {bg_color.clone()},
+            bg_fill = {event_background(&bg_color)},
+            onkeydown = {Callback::from(click_on_enter)},
+            text_color = {event_text_color(&bg_color)},
             ...
         )
     }
@@ -0,0 +1,308 @@
+use crate::prelude::*;
+
+/// Number of slots displayed in the ranked list
+const MAX_LISTED_SLOTS: usize = 15;
+
+fn local_ts(tz: Tz, date: NaiveDate, time: NaiveTime) -> Option<u64> {
+    tz.from_local_datetime(&date.and_time(time)).earliest().map(|dt| dt.timestamp() as u64)
+}
+
+/// Find the time slots, within the daily bounds in `tz`, during which no schedule has an event.
+/// Schedules are lists of busy periods (start and end timestamps), sorted by start time.
+pub fn find_free_slots(schedules: &[Vec<(u64, u64)>], from: NaiveDate, to: NaiveDate, bounds: (NaiveTime, NaiveTime), min_duration: u64, include_weekends: bool, tz: Tz) -> Vec<(u64, u64)> {
+    let mut slots = Vec::new();
+    let mut day = from;
+    while day <= to {
+        let weekend = matches!(day.weekday(), Weekday::Sat | Weekday::Sun);
+        if let (true, Some(start), Some(end)) = (include_weekends || !weekend, local_ts(tz, day, bounds.0), local_ts(tz, day, bounds.1)) {
+            // Collect busy periods of everyone
+            let mut busy = Vec::new();
+            for periods in schedules {
+                let first = periods.partition_point(|(period_start, _)| period_start + 86400 < start);
+                for (period_start, period_end) in periods[first..].iter().take_while(|(period_start, _)| *period_start < end) {
+                    if *period_end > start {
+                        busy.push((*period_start, *period_end));
+                    }
+                }
+            }
+            busy.sort();
+
+            // Free slots are the gaps between busy periods
+            let mut cursor = start;
+            for (busy_start, busy_end) in busy {
+                if busy_start > cursor && busy_start - cursor >= min_duration {
+                    slots.push((cursor, busy_start));
+                }
+                cursor = cursor.max(busy_end);
+            }
+            if end > cursor && end - cursor >= min_duration {
+                slots.push((cursor, end));
+            }
+        }
+        day = match day.succ_opt() {
+            Some(day) => day,
+            None => break,
+        };
+    }
+    slots
+}
+
+fn format_duration(seconds: u64) -> String {
+    match (seconds / 3600, seconds % 3600 / 60) {
+        (0, minutes) => format!("{minutes}min"),
+        (hours, 0) => format!("{hours}h"),
+        (hours, minutes) => format!("{hours}h{minutes:02}"),
+    }
+}
+
+pub struct FreeTimePage {
+    selected: Vec<i64>,
+    from: NaiveDate,
+    to: NaiveDate,
+    day_start: NaiveTime,
+    day_end: NaiveTime,
+    min_duration: u64,
+    include_weekends: bool,
+    /// Monday of the week displayed in the grid
+    grid_week: NaiveDate,
+}
+
+pub enum FreeTimeMsg {
+    ToggleFriend(i64, bool),
+    SetFrom(web_sys::Event),
+    SetTo(web_sys::Event),
+    SetDayStart(web_sys::Event),
+    SetDayEnd(web_sys::Event),
+    SetMinDuration(web_sys::Event),
+    IncludeWeekends(bool),
+    PreviousWeek,
+    NextWeek,
+}
+
+#[derive(Clone, Properties)]
+pub struct FreeTimeProps {
+    pub app_link: AppLink,
+    pub events: Rc<Vec<RawEvent>>,
+    pub friends: Rc<Option<FriendLists>>,
+    pub friends_events: FriendsEvents,
+}
+
+impl PartialEq for FreeTimeProps {
+    fn eq(&self, other: &Self) -> bool {
+        self.events == other.events && self.friends == other.friends && self.friends_events == other.friends_events
+    }
+}
+
+fn input_value(event: web_sys::Event) -> String {
+    let target = event.target().unwrap();
+    match target.dyn_ref::<HtmlInputElement>() {
+        Some(input) => input.value(),
+        None => target.dyn_into::<web_sys::HtmlSelectElement>().map(|select| select.value()).unwrap_or_default(),
+    }
+}
+
+impl Component for FreeTimePage {
+    type Message = FreeTimeMsg;
+    type Properties = FreeTimeProps;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        let today = Local::now().with_timezone(&user_tz()).date_naive();
+        FreeTimePage {
+            selected: Vec::new(),
+            from: today,
+            to: today + chrono::Duration::days(13),
+            day_start: NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+            day_end: NaiveTime::from_hms_opt(20, 0, 0).unwrap(),
+            min_duration: 3600,
+            include_weekends: false,
+            grid_week: today - chrono::Duration::days(days_from_week_start(today.weekday()) as i64),
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            FreeTimeMsg::ToggleFriend(uid, true) => {
+                // Make sure the friend's schedule is loaded and fresh
+                ctx.props().friends_events.get_events(uid, ctx.props().app_link.clone());
+                if !self.selected.contains(&uid) {
+                    self.selected.push(uid);
+                }
+            }
+            FreeTimeMsg::ToggleFriend(uid, false) => self.selected.retain(|u| *u != uid),
+            FreeTimeMsg::SetFrom(e) => {
+                let Ok(from) = NaiveDate::parse_from_str(&input_value(e), "%Y-%m-%d") else { return false };
+                self.from = from;
+                self.to = self.to.max(from);
+                self.grid_week = from - chrono::Duration::days(days_from_week_start(from.weekday()) as i64);
+            }
+            FreeTimeMsg::SetTo(e) => {
+                let Ok(to) = NaiveDate::parse_from_str(&input_value(e), "%Y-%m-%d") else { return false };
+                self.to = to.max(self.from);
+            }
+            FreeTimeMsg::SetDayStart(e) => {
+                let Ok(time) = NaiveTime::parse_from_str(&input_value(e), "%H:%M") else { return false };
+                self.day_start = time.min(self.day_end);
+            }
+            FreeTimeMsg::SetDayEnd(e) => {
+                let Ok(time) = NaiveTime::parse_from_str(&input_value(e), "%H:%M") else { return false };
+                self.day_end = time.max(self.day_start);
+            }
+            FreeTimeMsg::SetMinDuration(e) => {
+                let Ok(minutes) = input_value(e).parse::<u64>() else { return false };
+                self.min_duration = minutes * 60;
+            }
+            FreeTimeMsg::IncludeWeekends(include_weekends) => self.include_weekends = include_weekends,
+            FreeTimeMsg::PreviousWeek => self.grid_week -= chrono::Duration::days(7),
+            FreeTimeMsg::NextWeek => self.grid_week += chrono::Duration::days(7),
+        }
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let friends = match &*ctx.props().friends {
+            Some(friends) => friends,
+            None => return yew::virtual_dom::VNode::from_html_unchecked(AttrValue::from(include_str!("../friends/friends_loading.html"))),
+        };
+
+        // Gather the schedules of everyone
+        let mut schedules = vec![Rc::clone(&ctx.props().events)];
+        let mut loading_names = Vec::new();
+        for uid in &self.selected {
+            let Some(friend) = friends.friends.iter().find(|f| f.0.uid == *uid) else { continue };
+            match ctx.props().friends_events.get_cached(*uid) {
+                Some(events) => schedules.push(events),
+                None => loading_names.push(friend.0.email.trim_end_matches("@insa-rouen.fr").to_string()),
+            }
+        }
+        let busy_periods = schedules.iter().map(|events| events.iter().map(|e| (e.start_unixtime, e.end_unixtime)).collect::<Vec<_>>()).collect::<Vec<_>>();
+        let slots = find_free_slots(&busy_periods, self.from, self.to, (self.day_start, self.day_end), self.min_duration, self.include_weekends, user_tz());
+
+        // Ranked list: longest slots first, then earliest
+        let mut ranked = slots.clone();
+        ranked.sort_by_key(|(start, end)| (u64::MAX - (end - start), *start));
+        ranked.truncate(MAX_LISTED_SLOTS);
+        let has_slots = !ranked.is_empty();
+        let slot_iter = ranked.iter().map(|(start, end)| {
+            let start = user_tz().timestamp_opt(*start as i64, 0).unwrap();
+            let end = user_tz().timestamp_opt(*end as i64, 0).unwrap();
+            let time = format!("{} - {}", format_time(start), format_time(end));
+            tf("{day} : {time}", &[("day", &format_date(start.date_naive())), ("time", &time)])
+        }).collect::<Vec<_>>().into_iter();
+        let slot_duration_iter = ranked.iter().map(|(start, end)| format_duration(end - start)).collect::<Vec<_>>().into_iter();
+
+        // Week grid
+        let bounds_duration = (self.day_end - self.day_start).num_seconds().max(1) as f64;
+        let grid_days = (0..7).map(|i| self.grid_week + chrono::Duration::days(i)).filter(|day| self.include_weekends || !matches!(day.weekday(), Weekday::Sat | Weekday::Sun)).map(|day| {
+            let window_start = local_ts(user_tz(), day, self.day_start).unwrap_or_default();
+            let blocks = slots.iter().filter(|(start, _)| (window_start..window_start + 86400).contains(start)).map(|(start, end)| {
+                let top = 100.0 * (start - window_start) as f64 / bounds_duration;
+                let height = 100.0 * (end - start) as f64 / bounds_duration;
+                html! { <div class="free-time-slot" style={format!("top: {top}%; height: {height}%;")}></div> }
+            }).collect::<Html>();
+            let out_of_range = day < self.from || day > self.to;
+            html! {
+                <div class="free-time-day">
+                    <span>{format_day(day.weekday(), day.day())}</span>
+                    <div class={if out_of_range {"free-time-column free-time-out-of-range"} else {"free-time-column"}}>{blocks}</div>
+                </div>
+            }
+        }).collect::<Html>();
+
+        // Friend selection
+        let friend_checkboxes = friends.friends.iter().map(|friend| {
+            let uid = friend.0.uid;
+            let name = friend.0.email.trim_end_matches("@insa-rouen.fr").to_string();
+            html! {
+                <Checkbox message={name} checked={self.selected.contains(&uid)} onchange={ctx.link().callback(move |checked| FreeTimeMsg::ToggleFriend(uid, checked))} />
+            }
+        }).collect::<Html>();
+        let has_friends = !friends.friends.is_empty();
+        let weekends_checkbox = html! {
+            <Checkbox message={t("Inclure le week-end").to_string()} checked={self.include_weekends} onchange={ctx.link().callback(FreeTimeMsg::IncludeWeekends)} />
+        };
+
+        let is_loading = !loading_names.is_empty();
+        let loading_message = tf("Chargement de l'emploi du temps de {names}...", &[("names", &loading_names.join(", "))]);
+        let from = self.from.format("%Y-%m-%d").to_string();
+        let to = self.to.format("%Y-%m-%d").to_string();
+        let day_start = self.day_start.format("%H:%M").to_string();
+        let day_end = self.day_end.format("%H:%M").to_string();
+        let duration_value_iter = [30, 60, 90, 120, 180].into_iter().map(|m| m.to_string());
+        let duration_name_iter = [30, 60, 90, 120, 180].into_iter().map(|m| format_duration(m * 60));
+        let duration_selected_iter = [30, 60, 90, 120, 180].into_iter().map(|m| m * 60 == self.min_duration);
+
+        template_html!(
+            "src/free_time/free_time.html",
+            onchange_from = {ctx.link().callback(FreeTimeMsg::SetFrom)},
+            onchange_to = {ctx.link().callback(FreeTimeMsg::SetTo)},
+            onchange_day_start = {ctx.link().callback(FreeTimeMsg::SetDayStart)},
+            onchange_day_end = {ctx.link().callback(FreeTimeMsg::SetDayEnd)},
+            onchange_duration = {ctx.link().callback(FreeTimeMsg::SetMinDuration)},
+            onclick_previous_week = {ctx.link().callback(|_| FreeTimeMsg::PreviousWeek)},
+            onclick_next_week = {ctx.link().callback(|_| FreeTimeMsg::NextWeek)},
+            ...
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paris(d: u32, h: u32) -> u64 {
+        Paris.with_ymd_and_hms(2024, 3, d, h, 0, 0).unwrap().timestamp() as u64
+    }
+
+    fn date(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, d).unwrap()
+    }
+
+    fn time(h: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn merges_busy_periods_of_all_schedules() {
+        // Monday 4 March 2024
+        let mine = vec![(paris(4, 9), paris(4, 11)), (paris(4, 14), paris(4, 15))];
+        let friend = vec![(paris(4, 10), paris(4, 12)), (paris(4, 14), paris(4, 16))];
+        let slots = find_free_slots(&[mine, friend], date(4), date(4), (time(8), time(18)), 0, false, Paris);
+        assert_eq!(slots, vec![(paris(4, 8), paris(4, 9)), (paris(4, 12), paris(4, 14)), (paris(4, 16), paris(4, 18))]);
+    }
+
+    #[test]
+    fn day_bounds() {
+        // Periods crossing the bounds are cut, and the ones outside are ignored
+        let schedule = vec![(paris(4, 7), paris(4, 9)), (paris(4, 17), paris(4, 20)), (paris(4, 21), paris(4, 22))];
+        let slots = find_free_slots(&[schedule], date(4), date(4), (time(8), time(18)), 0, false, Paris);
+        assert_eq!(slots, vec![(paris(4, 9), paris(4, 17))]);
+
+        // A day without any free time
+        let schedule = vec![(paris(4, 8), paris(4, 18))];
+        assert_eq!(find_free_slots(&[schedule], date(4), date(4), (time(8), time(18)), 0, false, Paris), Vec::new());
+    }
+
+    #[test]
+    fn min_duration() {
+        let schedule = vec![(paris(4, 9), paris(4, 10)), (paris(4, 11), paris(4, 15))];
+        let slots = find_free_slots(&[schedule], date(4), date(4), (time(8), time(18)), 2 * 3600, false, Paris);
+        assert_eq!(slots, vec![(paris(4, 15), paris(4, 18))]);
+    }
+
+    #[test]
+    fn weekends() {
+        // Friday 8 to Monday 11 March 2024
+        let slots = find_free_slots(&[Vec::new()], date(8), date(11), (time(8), time(18)), 0, false, Paris);
+        assert_eq!(slots, vec![(paris(8, 8), paris(8, 18)), (paris(11, 8), paris(11, 18))]);
+
+        let slots = find_free_slots(&[Vec::new()], date(8), date(11), (time(8), time(18)), 0, true, Paris);
+        assert_eq!(slots.len(), 4);
+    }
+
+    #[test]
+    fn several_days() {
+        let schedule = vec![(paris(4, 8), paris(4, 17)), (paris(5, 10), paris(5, 18)), (paris(6, 8), paris(6, 18))];
+        let slots = find_free_slots(&[schedule], date(4), date(6), (time(8), time(18)), 0, false, Paris);
+        assert_eq!(slots, vec![(paris(4, 17), paris(4, 18)), (paris(5, 8), paris(5, 10))]);
+    }
+}
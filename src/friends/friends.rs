@@ -248,6 +248,8 @@ impl Component for FriendsPage {
             onclick_accept = { ctx.link().callback(|e| FriendsMsg::Accept(e)) },
             onclick_cancel = { ctx.link().callback(|e| FriendsMsg::Cancel(e)) },
             onclick_agenda = { ctx.link().callback(|e| FriendsMsg::Agenda(e)) },
+            onclick_friends_now = { ctx.props().app_link.callback(|_| AppMsg::SetPage(Page::FriendsNow)) },
+            onclick_free_time = { ctx.props().app_link.callback(|_| AppMsg::SetPage(Page::FreeTime)) },
             ...
         )
     }
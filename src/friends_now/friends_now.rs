@@ -0,0 +1,169 @@
+use crate::prelude::*;
+
+/// How far ahead to look for the next class, in seconds
+const LOOKAHEAD: u64 = 4 * 3600;
+
+/// Delay between two refreshes of the page, in milliseconds
+const REFRESH_INTERVAL: i32 = 60 * 1000;
+
+/// What a friend is doing at the moment
+enum FriendStatus<'a> {
+    InClass(&'a RawEvent),
+    Upcoming(&'a RawEvent),
+    Free,
+    Unknown,
+}
+
+fn friend_status(events: Option<&[RawEvent]>, now: u64) -> FriendStatus {
+    let Some(events) = events else { return FriendStatus::Unknown };
+    let first = events.partition_point(|e| e.end_unixtime <= now);
+    match events[first..].iter().find(|e| e.end_unixtime > now) {
+        Some(event) if event.start_unixtime <= now => FriendStatus::InClass(event),
+        Some(event) if event.start_unixtime < now + LOOKAHEAD => FriendStatus::Upcoming(event),
+        _ => FriendStatus::Free,
+    }
+}
+
+fn format_hour(timestamp: u64) -> String {
+    format_time(user_tz().timestamp_opt(timestamp as i64, 0).unwrap())
+}
+
+pub struct FriendsNowPage {
+    refresh_interval: Option<i32>,
+}
+
+pub enum FriendsNowMsg {
+    Refresh,
+    Agenda(MouseEvent),
+}
+
+#[derive(Clone, Properties)]
+pub struct FriendsNowProps {
+    pub app_link: AppLink,
+    pub friends: Rc<Option<FriendLists>>,
+    pub friends_events: FriendsEvents,
+}
+
+impl PartialEq for FriendsNowProps {
+    fn eq(&self, other: &Self) -> bool {
+        self.friends == other.friends && self.friends_events == other.friends_events
+    }
+}
+
+impl FriendsNowPage {
+    /// Fetch again the schedules that are missing or too old
+    fn refresh_schedules(ctx: &Context<Self>) {
+        let Some(friends) = ctx.props().friends.deref() else { return };
+        for (friend, _) in &friends.friends {
+            ctx.props().friends_events.get_events(friend.uid, ctx.props().app_link.clone());
+        }
+    }
+}
+
+impl Component for FriendsNowPage {
+    type Message = FriendsNowMsg;
+    type Properties = FriendsNowProps;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        Self::refresh_schedules(ctx);
+
+        // Refresh every minute so that statuses stay accurate
+        let link = ctx.link().clone();
+        let refresh = Closure::wrap(Box::new(move || {
+            link.send_message(FriendsNowMsg::Refresh);
+        }) as Box<dyn FnMut()>);
+        let refresh_interval = match window().set_interval_with_callback_and_timeout_and_arguments(refresh.as_ref().unchecked_ref(), REFRESH_INTERVAL, &Array::new()) {
+            Ok(id) => Some(id),
+            Err(e) => {
+                sentry_report(JsValue::from(&format!("Failed to set interval: {:?}", e)));
+                None
+            }
+        };
+        refresh.forget();
+
+        Self { refresh_interval }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            FriendsNowMsg::Refresh => {
+                Self::refresh_schedules(ctx);
+                true
+            },
+            FriendsNowMsg::Agenda(event) => {
+                let target = event.target().unwrap();
+                let el = target.dyn_into::<web_sys::Element>().unwrap();
+                let Some(pseudo) = el.closest("[data-pseudo]").ok().flatten().and_then(|el| el.get_attribute("data-pseudo")) else { return false };
+                ctx.props().app_link.send_message(AppMsg::SetPage(Page::FriendAgenda { pseudo }));
+                false
+            },
+        }
+    }
+
+    fn destroy(&mut self, _ctx: &Context<Self>) {
+        if let Some(id) = self.refresh_interval.take() {
+            window().clear_interval_with_handle(id);
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let friends = match &*ctx.props().friends {
+            Some(friends) => friends,
+            None => return yew::virtual_dom::VNode::from_html_unchecked(AttrValue::from(include_str!("../friends/friends_loading.html"))),
+        };
+
+        let now = now() as u64;
+        let friends_events = &ctx.props().friends_events;
+        let mut in_class = Vec::new();
+        let mut upcoming = Vec::new();
+        let mut free = Vec::new();
+        let mut unknown = Vec::new();
+        for (friend, _) in &friends.friends {
+            let pseudo = friend.email.trim_end_matches("@insa-rouen.fr").to_string();
+            let events = friends_events.get_cached(friend.uid);
+            let stale = if friends_events.is_stale(friend.uid) { format!(" ({})", t("peut ne pas être à jour")) } else { String::new() };
+            match friend_status(events.as_deref().map(|e| e.as_slice()), now) {
+                FriendStatus::InClass(event) => {
+                    let location = event.format_location().unwrap_or_else(|| t("Salle inconnue").to_string());
+                    let detail = format!("{} · {location} · {} {}{stale}", event.format_name(), t("jusqu'à"), format_hour(event.end_unixtime));
+                    in_class.push((friend.profile_url(), pseudo, detail));
+                }
+                FriendStatus::Upcoming(event) => {
+                    let location = event.format_location().unwrap_or_else(|| t("Salle inconnue").to_string());
+                    let detail = format!("{} {} · {} · {location}{stale}", t("À"), format_hour(event.start_unixtime), event.format_name());
+                    upcoming.push((friend.profile_url(), pseudo, detail));
+                }
+                FriendStatus::Free => free.push((friend.profile_url(), pseudo, stale.trim_start().to_string())),
+                FriendStatus::Unknown => unknown.push((friend.profile_url(), pseudo, t("Chargement...").to_string())),
+            }
+        }
+
+        let has_friends = !friends.friends.is_empty();
+        let has_in_class = !in_class.is_empty();
+        let class_picture_iter = in_class.iter().map(|f| f.0.clone());
+        let class_pseudo_iter = in_class.iter().map(|f| f.1.clone());
+        let class_name_iter = in_class.iter().map(|f| f.1.clone());
+        let class_detail_iter = in_class.iter().map(|f| f.2.clone());
+        let has_upcoming = !upcoming.is_empty();
+        let next_picture_iter = upcoming.iter().map(|f| f.0.clone());
+        let next_pseudo_iter = upcoming.iter().map(|f| f.1.clone());
+        let next_name_iter = upcoming.iter().map(|f| f.1.clone());
+        let next_detail_iter = upcoming.iter().map(|f| f.2.clone());
+        let has_free = !free.is_empty();
+        let free_picture_iter = free.iter().map(|f| f.0.clone());
+        let free_pseudo_iter = free.iter().map(|f| f.1.clone());
+        let free_name_iter = free.iter().map(|f| f.1.clone());
+        let free_detail_iter = free.iter().map(|f| f.2.clone());
+        let has_unknown = !unknown.is_empty();
+        let unknown_picture_iter = unknown.iter().map(|f| f.0.clone());
+        let unknown_name_iter = unknown.iter().map(|f| f.1.clone());
+        let unknown_detail_iter = unknown.iter().map(|f| f.2.clone());
+        let updated_at = tf("Mis à jour à {time}", &[("time", &format_hour(now))]);
+
+        template_html!(
+            "src/friends_now/friends_now.html",
+            onclick_agenda = { ctx.link().callback(FriendsNowMsg::Agenda) },
+            ...
+        )
+    }
+}
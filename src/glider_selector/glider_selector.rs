@@ -3,6 +3,8 @@ use crate::prelude::*;
 pub enum Msg {
     Init,
     Select(usize),
+    /// Selection of the previous or next value with the arrow keys
+    Key(KeyboardEvent),
 }
 
 #[derive(Properties, PartialEq, Clone)]
@@ -15,6 +17,9 @@ pub struct GliderSelectorProps {
     pub big: bool,
     #[prop_or_default]
     pub large: bool,
+    /// Id of the element that describes the selector, for screen readers
+    #[prop_or_default]
+    pub labelled_by: Option<&'static str>,
 }
 
 pub struct GliderSelector {
@@ -22,6 +27,8 @@ pub struct GliderSelector {
     sizes: Vec<u32>,
     selected: usize,
     id: String,
+    /// Whether the selected value should get the focus once rendered, after a keyboard selection
+    focus_selected: bool,
 }
 
 impl Component for GliderSelector {
@@ -42,6 +49,7 @@ impl Component for GliderSelector {
             id,
             sizes: Vec::new(),
             selected: ctx.props().selected,
+            focus_selected: false,
         }
     }
 
@@ -63,6 +71,23 @@ impl Component for GliderSelector {
                 self.selected = index;
                 true
             }
+            Msg::Key(event) => {
+                let last = ctx.props().values.len() - 1;
+                let index = match event.key().as_str() {
+                    "ArrowLeft" | "ArrowUp" => self.selected.checked_sub(1).unwrap_or(last),
+                    "ArrowRight" | "ArrowDown" => if self.selected == last { 0 } else { self.selected + 1 },
+                    "Home" => 0,
+                    "End" => last,
+                    _ => return false,
+                };
+                event.prevent_default();
+                if let Some(on_change) = &ctx.props().on_change {
+                    on_change.emit(index);
+                }
+                self.selected = index;
+                self.focus_selected = true;
+                true
+            }
         }
     }
 
@@ -73,6 +98,15 @@ impl Component for GliderSelector {
         true
     }
 
+    fn rendered(&mut self, _ctx: &Context<Self>, _first_render: bool) {
+        if std::mem::take(&mut self.focus_selected) {
+            let selected = window().doc().query_selector(&format!("#{} [aria-checked=true]", self.id)).ok().flatten();
+            if let Some(selected) = selected.and_then(|el| el.dyn_into::<HtmlElement>().ok()) {
+                let _ = selected.focus();
+            }
+        }
+    }
+
     fn view(&self, ctx: &Context<Self>) -> Html {
         let glider_selected = if self.sizes.len() == ctx.props().values.len() {
             let mut offset = 0;
@@ -82,7 +116,7 @@ impl Component for GliderSelector {
             let width = self.sizes[self.selected];
 
             html! {
-                <div class="glider-selected" style={format!("left: {}px; width: calc({}px - 2rem);", offset, width)}></div>
+                <div class="glider-selected" aria-hidden="true" style={format!("left: {}px; width: calc({}px - 2rem);", offset, width)}></div>
             }
         } else {
             let link2 = ctx.link().clone();
@@ -92,7 +126,7 @@ impl Component for GliderSelector {
             });
 
             html! {
-                <div class="glider-selected"></div>
+                <div class="glider-selected" aria-hidden="true"></div>
             }
         };
 
@@ -105,14 +139,15 @@ impl Component for GliderSelector {
         }
 
         html! {
-            <div class={classes} id={self.id.clone()}>
+            <div class={classes} id={self.id.clone()} role="radiogroup" aria-labelledby={ctx.props().labelled_by} onkeydown={ctx.link().callback(Msg::Key)}>
                 {glider_selected}
                 {
+                    // Only the selected value can be reached with Tab, the others are selected with the arrow keys
                     ctx.props().values.iter().enumerate().map(|(i, v)|
                         if i == self.selected {
-                            html! { <div style="color: var(--background);">{v}</div> }
+                            html! { <div role="radio" aria-checked="true" tabindex="0" style="color: var(--background);">{v}</div> }
                         } else {
-                            html! { <div onclick={
+                            html! { <div role="radio" aria-checked="false" tabindex="-1" onclick={
                                 let on_change = ctx.props().on_change.clone();
                                 ctx.link().callback(move |_| {
                                     if let Some(on_change) = &on_change {
@@ -0,0 +1,234 @@
+use crate::prelude::*;
+
+/// Classes starting within this number of seconds are compared when previewing other groups
+const PREVIEW_HORIZON: u64 = 28 * 86400;
+
+/// Number of upcoming classes of each course that are in `events` but not in `others`
+fn classes_not_in(events: &[RawEvent], others: &[RawEvent], now: u64) -> Vec<(String, u64)> {
+    let mut courses = BTreeMap::new();
+    for event in events.iter().filter(|e| e.start_unixtime > now && e.start_unixtime < now + PREVIEW_HORIZON) {
+        if !others.iter().any(|e| e.eid == event.eid) {
+            *courses.entry(event.format_name()).or_insert(0) += 1;
+        }
+    }
+    courses.into_iter().collect()
+}
+
+fn format_classes(classes: &[(String, u64)]) -> Vec<String> {
+    classes.iter().map(|(course, n)| tn("{course} : {n} séance", "{course} : {n} séances", *n, &[("course", course)])).collect()
+}
+
+pub enum GroupEditorMsg {
+    Loaded(GroupsResponse),
+    Select(String, web_sys::Event),
+    PreviewLoaded { request: usize, events: Vec<RawEvent> },
+    PreviewFailed { request: usize, error: ApiError },
+    RetryPreview,
+    Save,
+    Saved,
+    Failed(ApiError),
+    Close,
+}
+
+#[derive(Properties, Clone)]
+pub struct GroupEditorProps {
+    pub app_link: AppLink,
+    pub events: Rc<Vec<RawEvent>>,
+    pub on_close: Callback<()>,
+}
+
+impl PartialEq for GroupEditorProps {
+    fn eq(&self, other: &Self) -> bool {
+        self.events == other.events
+    }
+}
+
+/// Form to change the groups of the user, showing how their schedule would change before saving
+pub struct GroupEditor {
+    descriptors: Option<Vec<GroupDesc>>,
+    saved: GroupSelection,
+    selection: GroupSelection,
+    /// Schedule with the selected groups, once loaded
+    preview: Option<Vec<RawEvent>>,
+    /// Number of the last preview request, so that outdated responses are ignored
+    preview_request: usize,
+    /// Whether the last preview request failed
+    preview_failed: bool,
+    saving: bool,
+}
+
+impl GroupEditor {
+    fn request_preview(&mut self, ctx: &Context<Self>) {
+        self.preview = None;
+        self.preview_failed = false;
+        self.preview_request += 1;
+        if self.selection == self.saved {
+            return;
+        }
+        let request = self.preview_request;
+        let selection = self.selection.clone();
+        let link = ctx.link().clone();
+        spawn_local(async move {
+            match preview_groups_schedule(&selection).await {
+                Ok(events) => link.send_message(GroupEditorMsg::PreviewLoaded { request, events }),
+                Err(error) => link.send_message(GroupEditorMsg::PreviewFailed { request, error }),
+            }
+        });
+    }
+
+    /// Whether a value is missing for a required group
+    fn is_incomplete(&self) -> bool {
+        self.descriptors.iter().flatten().any(|desc| desc.required && !self.selection.contains_key(&desc.id))
+    }
+}
+
+impl Component for GroupEditor {
+    type Message = GroupEditorMsg;
+    type Properties = GroupEditorProps;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let link = ctx.link().clone();
+        spawn_local(async move {
+            match get_groups().await {
+                Ok(groups) => link.send_message(GroupEditorMsg::Loaded(groups)),
+                Err(e) => link.send_message(GroupEditorMsg::Failed(e)),
+            }
+        });
+
+        Self {
+            descriptors: None,
+            saved: GroupSelection::new(),
+            selection: GroupSelection::new(),
+            preview: None,
+            preview_request: 0,
+            preview_failed: false,
+            saving: false,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            GroupEditorMsg::Loaded(groups) => {
+                self.descriptors = Some(groups.descriptors);
+                self.saved = groups.selection.clone();
+                self.selection = groups.selection;
+                true
+            }
+            GroupEditorMsg::Select(id, event) => {
+                let Some(select) = event.target().and_then(|t| t.dyn_into::<web_sys::HtmlSelectElement>().ok()) else { return false };
+                match select.value() {
+                    value if value.is_empty() => self.selection.remove(&id),
+                    value => self.selection.insert(id, value),
+                };
+                self.request_preview(ctx);
+                true
+            }
+            GroupEditorMsg::PreviewLoaded { request, events } => {
+                if request != self.preview_request {
+                    return false;
+                }
+                self.preview = Some(events);
+                true
+            }
+            GroupEditorMsg::PreviewFailed { request, error } => {
+                if request != self.preview_request {
+                    return false;
+                }
+                error.handle_api_error();
+                self.preview_failed = true;
+                true
+            }
+            GroupEditorMsg::RetryPreview => {
+                self.request_preview(ctx);
+                true
+            }
+            GroupEditorMsg::Save => {
+                let Some(events) = self.preview.clone() else { return false };
+                if self.saving || self.is_incomplete() {
+                    return false;
+                }
+                self.saving = true;
+                let selection = self.selection.clone();
+                let app_link = ctx.props().app_link.clone();
+                let link = ctx.link().clone();
+                spawn_local(async move {
+                    match set_groups(&selection).await {
+                        Ok(()) => {
+                            app_link.send_message(AppMsg::GroupsChanged { selection, events });
+                            link.send_message(GroupEditorMsg::Saved);
+                        }
+                        Err(e) => link.send_message(GroupEditorMsg::Failed(e)),
+                    }
+                });
+                true
+            }
+            GroupEditorMsg::Saved => {
+                ctx.props().on_close.emit(());
+                false
+            }
+            GroupEditorMsg::Failed(e) => {
+                e.handle_api_error();
+                if self.descriptors.is_none() {
+                    ctx.props().on_close.emit(());
+                }
+                self.saving = false;
+                true
+            }
+            GroupEditorMsg::Close => {
+                ctx.props().on_close.emit(());
+                false
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let loading = self.descriptors.is_none();
+        let group_selectors = self.descriptors.iter().flatten().map(|desc| {
+            let selected = self.selection.get(&desc.id).cloned().unwrap_or_default();
+            let id = desc.id.clone();
+            html! {
+                <label class="group-selector">
+                    <span>{desc.name.clone()}</span>
+                    <select class="settings-select" onchange={ctx.link().callback(move |e| GroupEditorMsg::Select(id.clone(), e))}>
+                        if desc.required {
+                            <option value="" disabled=true selected={selected.is_empty()}>{t("Choisir...")}</option>
+                        } else {
+                            <option value="" selected={selected.is_empty()}>{t("Aucun")}</option>
+                        }
+                        { for desc.values.iter().map(|value| html! {
+                            <option value={value.clone()} selected={*value == selected}>{value.clone()}</option>
+                        }) }
+                    </select>
+                    <small>{desc.help.clone()}</small>
+                </label>
+            }
+        }).collect::<Html>();
+
+        let has_changes = self.selection != self.saved;
+        let preview_loading = has_changes && self.preview.is_none() && !self.preview_failed;
+        let preview_failed = has_changes && self.preview_failed;
+        let (added, removed) = match &self.preview {
+            Some(preview) => {
+                let now = now() as u64;
+                (classes_not_in(preview, &ctx.props().events, now), classes_not_in(&ctx.props().events, preview, now))
+            }
+            None => (Vec::new(), Vec::new()),
+        };
+        let has_added = !added.is_empty();
+        let has_removed = !removed.is_empty();
+        let no_impact = self.preview.is_some() && !has_added && !has_removed;
+        let added_iter = format_classes(&added).into_iter().map(|classes| format!("+ {classes}"));
+        let removed_iter = format_classes(&removed).into_iter().map(|classes| format!("− {classes}"));
+        let incomplete = self.is_incomplete();
+        let can_save = self.preview.is_some() && !incomplete && !self.saving;
+        let saving = self.saving;
+
+        template_html!(
+            "src/groups/groups.html",
+            onclick_save = {ctx.link().callback(|_| GroupEditorMsg::Save)},
+            onclick_retry = {ctx.link().callback(|_| GroupEditorMsg::RetryPreview)},
+            onclick_close = {ctx.link().callback(|_| GroupEditorMsg::Close)},
+            ...
+        )
+    }
+}
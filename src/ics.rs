@@ -0,0 +1,585 @@
+//! Export of events to the iCalendar format ([RFC 5545](https://www.rfc-editor.org/rfc/rfc5545)).
+//! The generated files can be imported in Google Calendar, Thunderbird, and most other calendar apps.
+
+use crate::prelude::*;
+
+/// Definition of the Europe/Paris time zone, referenced by all event times.
+const PARIS_VTIMEZONE: &[&str] = &[
+    "BEGIN:VTIMEZONE",
+    "TZID:Europe/Paris",
+    "BEGIN:DAYLIGHT",
+    "TZOFFSETFROM:+0100",
+    "TZOFFSETTO:+0200",
+    "TZNAME:CEST",
+    "DTSTART:19700329T020000",
+    "RRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU",
+    "END:DAYLIGHT",
+    "BEGIN:STANDARD",
+    "TZOFFSETFROM:+0200",
+    "TZOFFSETTO:+0100",
+    "TZNAME:CET",
+    "DTSTART:19701025T030000",
+    "RRULE:FREQ=YEARLY;BYMONTH=10;BYDAY=-1SU",
+    "END:STANDARD",
+    "END:VTIMEZONE",
+];
+
+/// Escape a value of type TEXT (section 3.3.11)
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => (),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Append a content line, folding it so that no line is longer than 75 octets (section 3.1)
+fn push_line(ics: &mut String, line: &str) {
+    let mut line_len = 0;
+    for c in line.chars() {
+        if line_len + c.len_utf8() > 75 {
+            ics.push_str("\r\n ");
+            line_len = 1;
+        }
+        ics.push(c);
+        line_len += c.len_utf8();
+    }
+    ics.push_str("\r\n");
+}
+
+fn format_paris_datetime(ts: u64) -> String {
+    Paris.timestamp_opt(ts as i64, 0).unwrap().format("%Y%m%dT%H%M%S").to_string()
+}
+
+fn push_event(ics: &mut String, event: &RawEvent, dtstamp: &str) {
+    push_line(ics, "BEGIN:VEVENT");
+    push_line(ics, &format!("UID:{}@insagenda.fr", escape_text(&event.eid)));
+    push_line(ics, &format!("DTSTAMP:{dtstamp}"));
+    push_line(ics, &format!("DTSTART;TZID=Europe/Paris:{}", format_paris_datetime(event.start_unixtime)));
+    push_line(ics, &format!("DTEND;TZID=Europe/Paris:{}", format_paris_datetime(event.end_unixtime)));
+    push_line(ics, &format!("SUMMARY:{}", escape_text(&event.format_name())));
+    if let Some(location) = event.format_location() {
+        push_line(ics, &format!("LOCATION:{}", escape_text(&location)));
+    }
+    if !event.teachers.is_empty() {
+        push_line(ics, &format!("DESCRIPTION:{}", escape_text(&event.teachers.join(", "))));
+    }
+    if let Some(kind) = &event.kind {
+        let category = match kind {
+            EventKind::Td => "TD",
+            EventKind::Tp => "TP",
+            EventKind::Cm => "CM",
+        };
+        push_line(ics, &format!("CATEGORIES:{category}"));
+    }
+    push_line(ics, "END:VEVENT");
+}
+
+/// Build a VCALENDAR containing one VEVENT per event
+pub fn events_to_ics<'a>(events: impl IntoIterator<Item = &'a RawEvent>) -> String {
+    let dtstamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+
+    let mut ics = String::new();
+    push_line(&mut ics, "BEGIN:VCALENDAR");
+    push_line(&mut ics, "VERSION:2.0");
+    push_line(&mut ics, "PRODID:-//INSAgenda//web-app//FR");
+    push_line(&mut ics, "CALSCALE:GREGORIAN");
+    push_line(&mut ics, "METHOD:PUBLISH");
+    push_line(&mut ics, "X-WR-CALNAME:INSAgenda");
+    push_line(&mut ics, "X-WR-TIMEZONE:Europe/Paris");
+    for line in PARIS_VTIMEZONE {
+        push_line(&mut ics, line);
+    }
+    for event in events {
+        push_event(&mut ics, event, &dtstamp);
+    }
+    push_line(&mut ics, "END:VCALENDAR");
+
+    ics
+}
+
+/// Make the browser download the given iCalendar content
+pub fn download_ics(filename: &str, ics: &str) {
+    if let Err(e) = download_file(filename, "text/calendar;charset=utf-8", ics) {
+        sentry_report(e);
+        alert(t("Impossible d'exporter le calendrier."));
+    }
+}
+
+/// Occurrences of recurring events are only generated up to this number of seconds after now
+const RECURRENCE_HORIZON: i64 = 366 * 86400;
+/// Maximum number of occurrences generated for a single recurring event
+const MAX_OCCURRENCES: usize = 1000;
+
+/// A content line, split into its name, parameters and value
+struct ContentLine {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl ContentLine {
+    fn parse(line: &str) -> Option<ContentLine> {
+        // The value starts after the first colon that is not quoted
+        let mut in_quotes = false;
+        let colon = line.char_indices().find(|(_, c)| {
+            if *c == '"' { in_quotes = !in_quotes; }
+            *c == ':' && !in_quotes
+        })?.0;
+        let (head, value) = (&line[..colon], &line[colon + 1..]);
+
+        let mut parts = head.split(';');
+        let name = parts.next()?.to_uppercase();
+        let params = parts.filter_map(|param| {
+            let (key, value) = param.split_once('=')?;
+            Some((key.to_uppercase(), value.trim_matches('"').to_string()))
+        }).collect();
+
+        Some(ContentLine { name, params, value: value.to_string() })
+    }
+
+    fn param(&self, key: &str) -> Option<&str> {
+        self.params.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+}
+
+/// Reverse of `escape_text`
+fn unescape_text(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match (c, c == '\\') {
+            (_, true) => match chars.next() {
+                Some('n') | Some('N') => unescaped.push('\n'),
+                Some(c) => unescaped.push(c),
+                None => (),
+            },
+            (c, false) => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// A date-time value, kept in the time zone it was written in so that recurrences follow wall clock time across DST changes
+#[derive(Clone, Copy)]
+struct IcsDateTime {
+    local: NaiveDateTime,
+    tz: Tz,
+    all_day: bool,
+}
+
+impl IcsDateTime {
+    fn parse(line: &ContentLine) -> Option<IcsDateTime> {
+        let value = line.value.trim();
+        if line.param("VALUE") == Some("DATE") || value.len() == 8 {
+            let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+            return Some(IcsDateTime { local: date.and_hms_opt(0, 0, 0)?, tz: Paris, all_day: true });
+        }
+        if let Some(utc) = value.strip_suffix('Z') {
+            let utc = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+            let local = Utc.from_utc_datetime(&utc).with_timezone(&Paris).naive_local();
+            return Some(IcsDateTime { local, tz: Paris, all_day: false });
+        }
+        let local = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+        let tz = line.param("TZID").and_then(|tzid| tzid.parse::<Tz>().ok()).unwrap_or(Paris);
+        Some(IcsDateTime { local, tz, all_day: false })
+    }
+
+    fn with_local(self, local: NaiveDateTime) -> IcsDateTime {
+        IcsDateTime { local, ..self }
+    }
+
+    fn timestamp(&self) -> Option<i64> {
+        // Times that do not exist because of a DST change are shifted by an hour
+        self.tz.from_local_datetime(&self.local).earliest()
+            .or_else(|| self.tz.from_local_datetime(&(self.local + chrono::Duration::hours(1))).earliest())
+            .map(|dt| dt.timestamp())
+    }
+}
+
+/// Parse a duration value (section 3.3.6), such as `PT1H30M` or `P1W`
+fn parse_duration(value: &str) -> Option<i64> {
+    let (sign, value) = match value.strip_prefix('-') {
+        Some(value) => (-1, value),
+        None => (1, value.trim_start_matches('+')),
+    };
+    let value = value.strip_prefix('P')?;
+    let mut seconds = 0;
+    let mut number = String::new();
+    for c in value.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => (),
+            unit => {
+                let n: i64 = number.parse().ok()?;
+                number.clear();
+                seconds += n * match unit {
+                    'W' => 7 * 86400,
+                    'D' => 86400,
+                    'H' => 3600,
+                    'M' => 60,
+                    'S' => 1,
+                    _ => return None,
+                };
+            }
+        }
+    }
+    Some(sign * seconds)
+}
+
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// The subset of RRULE (section 3.3.10) supported by the importer.
+/// BYDAY can only have ordinals (such as `2TU`) in monthly rules, and BYMONTHDAY is only supported in monthly rules.
+struct RecurrenceRule {
+    frequency: Frequency,
+    interval: u32,
+    count: Option<usize>,
+    until: Option<i64>,
+    /// Days of the week, with their ordinal in the month if any (`-1` for the last one)
+    by_day: Vec<(Option<i32>, Weekday)>,
+    /// Days of the month, negative ones counting from the end of the month
+    by_month_day: Vec<i32>,
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    let next_month = match month {
+        12 => NaiveDate::from_ymd_opt(year + 1, 1, 1),
+        month => NaiveDate::from_ymd_opt(year, month + 1, 1),
+    };
+    next_month.and_then(|date| date.pred_opt()).map(|date| date.day()).unwrap_or(31)
+}
+
+impl RecurrenceRule {
+    /// Parse a rule, returning `None` if it is invalid or uses parts that aren't supported
+    fn parse(value: &str, tz: Tz) -> Option<RecurrenceRule> {
+        let mut frequency = None;
+        let mut rule = RecurrenceRule { frequency: Frequency::Daily, interval: 1, count: None, until: None, by_day: Vec::new(), by_month_day: Vec::new() };
+        for part in value.split(';') {
+            let Some((key, value)) = part.split_once('=') else { continue };
+            match key.to_uppercase().as_str() {
+                "FREQ" => frequency = Some(match value.to_uppercase().as_str() {
+                    "DAILY" => Frequency::Daily,
+                    "WEEKLY" => Frequency::Weekly,
+                    "MONTHLY" => Frequency::Monthly,
+                    "YEARLY" => Frequency::Yearly,
+                    _ => return None,
+                }),
+                "INTERVAL" => rule.interval = value.parse().ok().filter(|i| *i > 0)?,
+                "COUNT" => rule.count = value.parse().ok(),
+                "UNTIL" => {
+                    let line = ContentLine { name: String::from("UNTIL"), params: vec![(String::from("TZID"), tz.name().to_string())], value: value.to_string() };
+                    let until = IcsDateTime::parse(&line)?;
+                    // A date-only UNTIL includes the whole day
+                    let end_of_day = if until.all_day { 86399 } else { 0 };
+                    rule.until = until.timestamp().map(|ts| ts + end_of_day);
+                }
+                "BYDAY" => for day in value.split(',') {
+                    let (ordinal, day) = day.split_at(day.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(day.len()));
+                    let ordinal = match ordinal {
+                        "" => None,
+                        ordinal => Some(ordinal.trim_start_matches('+').parse::<i32>().ok().filter(|n| *n != 0 && n.abs() <= 5)?),
+                    };
+                    let day = match day.to_uppercase().as_str() {
+                        "MO" => Weekday::Mon,
+                        "TU" => Weekday::Tue,
+                        "WE" => Weekday::Wed,
+                        "TH" => Weekday::Thu,
+                        "FR" => Weekday::Fri,
+                        "SA" => Weekday::Sat,
+                        "SU" => Weekday::Sun,
+                        _ => return None,
+                    };
+                    rule.by_day.push((ordinal, day));
+                },
+                "BYMONTHDAY" => for day in value.split(',') {
+                    rule.by_month_day.push(day.trim_start_matches('+').parse::<i32>().ok().filter(|n| *n != 0 && n.abs() <= 31)?);
+                },
+                // Week numbering only matters for parts that aren't supported
+                "WKST" => (),
+                _ => return None,
+            }
+        }
+        rule.frequency = frequency?;
+
+        let has_ordinals = rule.by_day.iter().any(|(ordinal, _)| ordinal.is_some());
+        match rule.frequency {
+            Frequency::Daily | Frequency::Weekly if has_ordinals || !rule.by_month_day.is_empty() => None,
+            Frequency::Yearly if !rule.by_day.is_empty() || !rule.by_month_day.is_empty() => None,
+            _ => Some(rule),
+        }
+    }
+
+    /// First day of the `period`-th repetition of the rule, before which it has no candidate
+    fn period_start(&self, start: NaiveDate, period: u32) -> NaiveDate {
+        let n = period * self.interval;
+        match self.frequency {
+            Frequency::Daily => start + chrono::Duration::days(n as i64),
+            Frequency::Weekly => start - chrono::Duration::days(start.weekday().num_days_from_monday() as i64) + chrono::Duration::weeks(n as i64),
+            Frequency::Monthly => {
+                let months = start.month0() + n;
+                NaiveDate::from_ymd_opt(start.year() + (months / 12) as i32, months % 12 + 1, 1).unwrap_or(NaiveDate::MAX)
+            }
+            Frequency::Yearly => NaiveDate::from_ymd_opt(start.year() + n as i32, 1, 1).unwrap_or(NaiveDate::MAX),
+        }
+    }
+
+    /// Candidate start dates for the `period`-th repetition of the rule, in chronological order
+    fn candidates(&self, start: NaiveDate, period: u32) -> Vec<NaiveDate> {
+        let n = period * self.interval;
+        match self.frequency {
+            Frequency::Daily => {
+                let day = start + chrono::Duration::days(n as i64);
+                let on_day = self.by_day.is_empty() || self.by_day.iter().any(|(_, d)| *d == day.weekday());
+                on_day.then_some(day).into_iter().collect()
+            }
+            Frequency::Weekly if self.by_day.is_empty() => vec![start + chrono::Duration::weeks(n as i64)],
+            Frequency::Weekly => {
+                let monday = self.period_start(start, period);
+                let mut days: Vec<_> = self.by_day.iter().map(|(_, d)| monday + chrono::Duration::days(d.num_days_from_monday() as i64)).collect();
+                days.sort();
+                days.dedup();
+                days
+            }
+            Frequency::Monthly if self.by_day.is_empty() && self.by_month_day.is_empty() => {
+                let month = self.period_start(start, period);
+                month.with_day(start.day()).into_iter().collect()
+            }
+            Frequency::Monthly => {
+                let month = self.period_start(start, period);
+                let length = days_in_month(month.year(), month.month());
+                (1..=length).filter_map(|day| month.with_day(day)).filter(|date| {
+                    let day = date.day() as i32;
+                    let on_month_day = self.by_month_day.is_empty() || self.by_month_day.iter().any(|d| *d == day || *d == day - length as i32 - 1);
+                    let on_day = self.by_day.is_empty() || self.by_day.iter().any(|(ordinal, d)| *d == date.weekday() && match ordinal {
+                        None => true,
+                        Some(n) if *n > 0 => (day - 1) / 7 + 1 == *n,
+                        Some(n) => (length as i32 - day) / 7 + 1 == -n,
+                    });
+                    on_month_day && on_day
+                }).collect()
+            }
+            Frequency::Yearly => NaiveDate::from_ymd_opt(start.year() + n as i32, start.month(), start.day()).into_iter().collect(),
+        }
+    }
+}
+
+/// Generate the start timestamps of all occurrences of an event, up to the `horizon` timestamp
+fn expand_occurrences(start: IcsDateTime, rule: Option<&RecurrenceRule>, exdates: &[i64], horizon: i64) -> Vec<i64> {
+    let Some(first) = start.timestamp() else { return Vec::new() };
+    let Some(rule) = rule else { return vec![first] };
+
+    let mut occurrences = Vec::new();
+    let mut generated = 0;
+    for period in 0.. {
+        // Periods can have no candidate, such as months without a fifth Tuesday, so the loop ends with their start
+        let period_start = rule.period_start(start.local.date(), period);
+        if start.with_local(period_start.and_hms_opt(0, 0, 0).unwrap()).timestamp().unwrap_or(i64::MAX) > horizon {
+            break;
+        }
+        for date in rule.candidates(start.local.date(), period) {
+            let Some(ts) = start.with_local(date.and_time(start.local.time())).timestamp() else { continue };
+            if ts < first {
+                continue;
+            }
+            if rule.until.map(|until| ts > until).unwrap_or(false) || ts > horizon {
+                return occurrences;
+            }
+            generated += 1;
+            if !exdates.contains(&ts) {
+                occurrences.push(ts);
+            }
+            if rule.count.map(|count| generated >= count).unwrap_or(false) || generated >= MAX_OCCURRENCES {
+                return occurrences;
+            }
+        }
+    }
+    occurrences
+}
+
+/// Parse an iCalendar file, expanding recurring events into separate occurrences.
+/// Cancelled events and overridden occurrences (RECURRENCE-ID) are ignored.
+/// Events whose repetition rule isn't supported are left out, and counted in the second value.
+pub fn parse_ics(content: &str, source: &str) -> Result<(Vec<ImportedEvent>, usize), String> {
+    // Unfold lines (section 3.1)
+    let mut lines: Vec<String> = Vec::new();
+    for line in content.lines() {
+        match line.strip_prefix(' ').or_else(|| line.strip_prefix('\t')) {
+            Some(continuation) if !lines.is_empty() => lines.last_mut().unwrap().push_str(continuation),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    if !lines.first().map(|l| l.trim().eq_ignore_ascii_case("BEGIN:VCALENDAR")).unwrap_or(false) {
+        return Err(String::from("not an iCalendar file"));
+    }
+
+    let mut events = Vec::new();
+    let mut unsupported = 0;
+    let mut current: Option<Vec<ContentLine>> = None;
+    let mut nested_depth = 0;
+    for line in lines.iter().filter_map(|line| ContentLine::parse(line)) {
+        match (line.name.as_str(), line.value.to_uppercase().as_str(), current.is_some()) {
+            ("BEGIN", "VEVENT", false) => current = Some(Vec::new()),
+            ("END", "VEVENT", true) if nested_depth == 0 => {
+                let properties = current.take().unwrap();
+                match vevent_to_imported_events(&properties, source, events.len()) {
+                    Some(occurrences) => events.extend(occurrences),
+                    None => unsupported += 1,
+                }
+            }
+            ("BEGIN", _, true) => nested_depth += 1,
+            ("END", _, true) => nested_depth -= 1,
+            (_, _, true) if nested_depth == 0 => current.as_mut().unwrap().push(line),
+            _ => (),
+        }
+    }
+
+    events.sort_by_key(|e: &ImportedEvent| e.start_unixtime);
+    Ok((events, unsupported))
+}
+
+/// Occurrences of an event, or `None` if its repetition rule isn't supported
+fn vevent_to_imported_events(properties: &[ContentLine], source: &str, index: usize) -> Option<Vec<ImportedEvent>> {
+    let get = |name: &str| properties.iter().find(|p| p.name == name);
+
+    if get("RECURRENCE-ID").is_some() || get("STATUS").map(|s| s.value.eq_ignore_ascii_case("CANCELLED")).unwrap_or(false) {
+        return Some(Vec::new());
+    }
+    let Some(start) = get("DTSTART").and_then(IcsDateTime::parse) else { return Some(Vec::new()) };
+    let Some(first) = start.timestamp() else { return Some(Vec::new()) };
+    let duration = match (get("DTEND").and_then(IcsDateTime::parse).and_then(|end| end.timestamp()), get("DURATION").and_then(|d| parse_duration(&d.value))) {
+        (Some(end), _) => end - first,
+        (None, Some(duration)) => duration,
+        (None, None) if start.all_day => 86400,
+        (None, None) => 0,
+    };
+    let rule = match get("RRULE") {
+        Some(rule) => Some(RecurrenceRule::parse(&rule.value, start.tz)?),
+        None => None,
+    };
+    let exdates: Vec<i64> = properties.iter().filter(|p| p.name == "EXDATE").flat_map(|p| {
+        p.value.split(',').filter_map(|value| {
+            let line = ContentLine { name: String::from("EXDATE"), params: p.params.clone(), value: value.to_string() };
+            IcsDateTime::parse(&line).and_then(|exdate| exdate.timestamp())
+        }).collect::<Vec<_>>()
+    }).collect();
+
+    let uid = get("UID").map(|uid| uid.value.clone()).unwrap_or_else(|| format!("{source}-{index}"));
+    let summary = get("SUMMARY").map(|s| unescape_text(&s.value)).unwrap_or_default();
+    let location = get("LOCATION").map(|l| unescape_text(&l.value)).filter(|l| !l.is_empty());
+    let description = get("DESCRIPTION").map(|d| unescape_text(&d.value)).filter(|d| !d.is_empty());
+
+    let occurrences = expand_occurrences(start, rule.as_ref(), &exdates, now() + RECURRENCE_HORIZON).into_iter().map(|ts| ImportedEvent {
+        uid: format!("{uid}/{ts}"),
+        source: source.to_string(),
+        summary: summary.clone(),
+        location: location.clone(),
+        description: description.clone(),
+        start_unixtime: ts as u64,
+        end_unixtime: (ts + duration.max(0)) as u64,
+        all_day: start.all_day,
+    }).collect();
+    Some(occurrences)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paris(y: i32, m: u32, d: u32, h: u32) -> i64 {
+        Paris.with_ymd_and_hms(y, m, d, h, 0, 0).unwrap().timestamp()
+    }
+
+    fn expand(dtstart: &str, rrule: &str, exdates: &[i64]) -> Vec<i64> {
+        let start = IcsDateTime::parse(&ContentLine::parse(dtstart).unwrap()).unwrap();
+        let rule = RecurrenceRule::parse(rrule, start.tz).unwrap();
+        expand_occurrences(start, Some(&rule), exdates, paris(2025, 1, 1, 0))
+    }
+
+    #[test]
+    fn count() {
+        let occurrences = expand("DTSTART;TZID=Europe/Paris:20240101T100000", "FREQ=DAILY;COUNT=3", &[]);
+        assert_eq!(occurrences, vec![paris(2024, 1, 1, 10), paris(2024, 1, 2, 10), paris(2024, 1, 3, 10)]);
+    }
+
+    #[test]
+    fn count_includes_exdates() {
+        let occurrences = expand("DTSTART;TZID=Europe/Paris:20240101T100000", "FREQ=DAILY;COUNT=3", &[paris(2024, 1, 2, 10)]);
+        assert_eq!(occurrences, vec![paris(2024, 1, 1, 10), paris(2024, 1, 3, 10)]);
+    }
+
+    #[test]
+    fn until() {
+        // A date-only UNTIL includes the occurrence of that day
+        let occurrences = expand("DTSTART;TZID=Europe/Paris:20240101T100000", "FREQ=WEEKLY;UNTIL=20240122", &[]);
+        assert_eq!(occurrences, vec![paris(2024, 1, 1, 10), paris(2024, 1, 8, 10), paris(2024, 1, 15, 10), paris(2024, 1, 22, 10)]);
+
+        let occurrences = expand("DTSTART:20240101T090000Z", "FREQ=DAILY;UNTIL=20240102T090000Z", &[]);
+        assert_eq!(occurrences, vec![paris(2024, 1, 1, 10), paris(2024, 1, 2, 10)]);
+    }
+
+    #[test]
+    fn by_day() {
+        let occurrences = expand("DTSTART;TZID=Europe/Paris:20240101T100000", "FREQ=WEEKLY;BYDAY=MO,WE;COUNT=4", &[]);
+        assert_eq!(occurrences, vec![paris(2024, 1, 1, 10), paris(2024, 1, 3, 10), paris(2024, 1, 8, 10), paris(2024, 1, 10, 10)]);
+
+        // Days of the first week that are before the start are skipped
+        let occurrences = expand("DTSTART;TZID=Europe/Paris:20240103T100000", "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE;COUNT=3", &[]);
+        assert_eq!(occurrences, vec![paris(2024, 1, 3, 10), paris(2024, 1, 15, 10), paris(2024, 1, 17, 10)]);
+    }
+
+    #[test]
+    fn monthly_by_day() {
+        // Second Tuesday of the month
+        let occurrences = expand("DTSTART;TZID=Europe/Paris:20240109T100000", "FREQ=MONTHLY;BYDAY=2TU;COUNT=3", &[]);
+        assert_eq!(occurrences, vec![paris(2024, 1, 9, 10), paris(2024, 2, 13, 10), paris(2024, 3, 12, 10)]);
+
+        // Last Friday of the month
+        let occurrences = expand("DTSTART;TZID=Europe/Paris:20240126T100000", "FREQ=MONTHLY;BYDAY=-1FR;COUNT=3", &[]);
+        assert_eq!(occurrences, vec![paris(2024, 1, 26, 10), paris(2024, 2, 23, 10), paris(2024, 3, 29, 10)]);
+    }
+
+    #[test]
+    fn monthly_by_month_day() {
+        let occurrences = expand("DTSTART;TZID=Europe/Paris:20240131T100000", "FREQ=MONTHLY;BYMONTHDAY=-1;COUNT=3", &[]);
+        assert_eq!(occurrences, vec![paris(2024, 1, 31, 10), paris(2024, 2, 29, 10), paris(2024, 3, 31, 10)]);
+
+        // Rules that never match end at the horizon
+        let occurrences = expand("DTSTART;TZID=Europe/Paris:20240101T100000", "FREQ=MONTHLY;BYMONTHDAY=1;BYDAY=2MO", &[]);
+        assert_eq!(occurrences, Vec::<i64>::new());
+    }
+
+    #[test]
+    fn unsupported_parts() {
+        assert!(RecurrenceRule::parse("FREQ=MONTHLY;BYDAY=TU;BYSETPOS=-1", Paris).is_none());
+        assert!(RecurrenceRule::parse("FREQ=WEEKLY;BYDAY=1MO", Paris).is_none());
+        assert!(RecurrenceRule::parse("FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU", Paris).is_none());
+        assert!(RecurrenceRule::parse("FREQ=DAILY;BYMONTHDAY=1", Paris).is_none());
+        assert!(RecurrenceRule::parse("FREQ=WEEKLY;BYDAY=MO;WKST=SU", Paris).is_some());
+    }
+
+    #[test]
+    fn wall_clock_time_across_dst() {
+        let occurrences = expand("DTSTART;TZID=Europe/Paris:20240330T100000", "FREQ=DAILY;COUNT=2", &[]);
+        assert_eq!(occurrences, vec![paris(2024, 3, 30, 10), paris(2024, 3, 31, 10)]);
+        assert_eq!(occurrences[1] - occurrences[0], 23 * 3600);
+    }
+
+    #[test]
+    fn horizon() {
+        let occurrences = expand("DTSTART;TZID=Europe/Paris:20241230T100000", "FREQ=DAILY", &[]);
+        assert_eq!(occurrences, vec![paris(2024, 12, 30, 10), paris(2024, 12, 31, 10)]);
+    }
+}
@@ -0,0 +1,120 @@
+use crate::prelude::*;
+
+/// An event imported from an external iCalendar file.
+/// These events are only stored locally and displayed over the schedule.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct ImportedEvent {
+    pub uid: String,
+    /// Name of the file the event was imported from
+    pub source: String,
+    pub summary: String,
+    pub location: Option<String>,
+    pub description: Option<String>,
+    pub start_unixtime: u64,
+    pub end_unixtime: u64,
+    pub all_day: bool,
+}
+
+impl ImportedEvent {
+    /// Whether the event is ongoing at some point of the range, so that events lasting several days are displayed on each of them
+    pub fn overlaps(&self, range: &std::ops::Range<u64>) -> bool {
+        self.start_unixtime < range.end && self.end_unixtime.max(self.start_unixtime + 1) > range.start
+    }
+}
+
+/// All imported events, sorted by start time.
+#[derive(Default, Clone)]
+pub struct ImportedCalendars {
+    events: Rc<Vec<ImportedEvent>>,
+}
+
+impl ImportedCalendars {
+    pub fn init() -> Self {
+        let local_storage = window().local_storage().unwrap().unwrap();
+
+        let Ok(Some(cached_str)) = local_storage.get("imported_events") else { return Self::default() };
+        let Ok(mut events) = serde_json::from_str::<Vec<ImportedEvent>>(&cached_str) else { return Self::default() };
+        events.sort_by_key(|e| e.start_unixtime);
+
+        Self { events: Rc::new(events) }
+    }
+
+    fn save(&self) {
+        let local_storage = window().local_storage().unwrap().unwrap();
+        local_storage.set("imported_events", &serde_json::to_string(self.events.as_ref()).unwrap()).unwrap();
+    }
+
+    pub fn events(&self) -> Rc<Vec<ImportedEvent>> {
+        Rc::clone(&self.events)
+    }
+
+    /// Names of the imported files, in import order
+    pub fn sources(&self) -> Vec<String> {
+        let mut sources: Vec<String> = Vec::new();
+        for event in self.events.iter() {
+            if !sources.contains(&event.source) {
+                sources.push(event.source.clone());
+            }
+        }
+        sources
+    }
+
+    /// Add the events of a file, replacing those previously imported from a file with the same name
+    pub fn insert(&mut self, source: &str, new_events: Vec<ImportedEvent>) {
+        let mut events = self.events.as_ref().clone();
+        events.retain(|e| e.source != source);
+        events.extend(new_events);
+        events.sort_by_key(|e| e.start_unixtime);
+        self.events = Rc::new(events);
+        self.save();
+    }
+
+    pub fn remove(&mut self, source: &str) {
+        let mut events = self.events.as_ref().clone();
+        events.retain(|e| e.source != source);
+        self.events = Rc::new(events);
+        self.save();
+    }
+}
+
+#[derive(Properties, Clone, PartialEq)]
+pub struct ImportedEventCompProps {
+    pub event: ImportedEvent,
+    pub day_start: u64,
+    /// Column of the event and number of columns, when it overlaps other events
+    pub column: (usize, usize),
+}
+
+pub struct ImportedEventComp {}
+
+impl Component for ImportedEventComp {
+    type Message = ();
+    type Properties = ImportedEventCompProps;
+
+    fn create(_ctx: &Context<Self>) -> Self { Self {} }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let event = &ctx.props().event;
+
+        // Calculate position (all-day events are displayed over the whole day)
+        let day_sec_count = 43200.0;
+        let (start, end) = match event.all_day {
+            true => (ctx.props().day_start + 8 * 3600, ctx.props().day_start + 20 * 3600),
+            false => (event.start_unixtime.max(ctx.props().day_start + 8 * 3600), event.end_unixtime.min(ctx.props().day_start + 20 * 3600)),
+        };
+        if start >= end {
+            return html!{};
+        }
+        let percent_offset = 100.0 / day_sec_count * (start - (ctx.props().day_start + 8 * 3600)) as f64;
+        let percent_height = 100.0 / day_sec_count * (end - start) as f64;
+        let (column, column_count) = ctx.props().column;
+        let percent_left = 100.0 * column as f64 / column_count as f64;
+        let percent_width = 100.0 / column_count as f64;
+
+        let name = event.summary.as_str();
+        let opt_location = event.location.as_deref();
+        let title = event.description.clone().unwrap_or_default();
+        let source = event.source.as_str();
+        template_html!("src/imported/imported_event.html", ...)
+    }
+}
@@ -29,16 +29,36 @@ mod sortable;
 mod tabbar;
 #[path = "friends/friends.rs"]
 mod friends;
+#[path = "free_time/free_time.rs"]
+mod free_time;
+#[path = "friends_now/friends_now.rs"]
+mod friends_now;
 #[path = "comment/comment.rs"]
 mod comment;
 #[path = "notifications/notifications.rs"]
 mod notifications;
+#[path = "imported/imported.rs"]
+mod imported;
+#[path = "personal/personal.rs"]
+mod personal;
+#[path = "groups/groups.rs"]
+mod groups;
 mod util;
+mod reminders;
+mod push;
 mod slider;
 mod api;
 mod prelude;
 mod translation;
+mod po;
 mod colors;
+mod ics;
+mod datetime;
+mod badge;
+mod archive;
+mod account;
+mod accessibility;
+mod rich_text;
 
 use slider::width;
 
@@ -51,6 +71,8 @@ pub enum Page {
     Agenda,
     Friends,
     FriendAgenda { pseudo: String },
+    FreeTime,
+    FriendsNow,
     Notifications,
     Event { eid: String },
     Survey { sid: String },
@@ -63,11 +85,28 @@ impl Page {
             Page::Agenda => (String::from("agenda"), "Agenda"),
             Page::Friends => (String::from("friends"), "Friends"),
             Page::FriendAgenda { pseudo } => (format!("friend-agenda/{pseudo}"), "Friend agenda"),
+            Page::FreeTime => (String::from("free-time"), "Free time"),
+            Page::FriendsNow => (String::from("friends-now"), "Where are my friends"),
             Page::Notifications => (String::from("notifications"), "Notifications"),
             Page::Survey { sid } => (format!("survey/{sid}"), "Survey"),
             Page::Event { eid } => (format!("event/{eid}"), "Event"),
         }
     }
+
+    /// Name of the page, announced to screen readers when it opens
+    fn name(&self) -> String {
+        match self {
+            Page::Settings => t("Paramètres").to_string(),
+            Page::Agenda => t("Agenda").to_string(),
+            Page::Friends => t("Amis").to_string(),
+            Page::FriendAgenda { pseudo } => tf("Agenda de {pseudo}", &[("pseudo", pseudo)]),
+            Page::FreeTime => t("Temps libre commun").to_string(),
+            Page::FriendsNow => t("Où sont mes amis").to_string(),
+            Page::Notifications => t("Notifications").to_string(),
+            Page::Survey { .. } => t("Sondage").to_string(),
+            Page::Event { .. } => t("Détails du cours").to_string(),
+        }
+    }
 }
 
 /// A message that can be sent to the `App` component.
@@ -80,6 +119,13 @@ pub enum Msg {
     SaveSurveyAnswer(SurveyAnswers),
     UpdateFriends(FriendLists),
     MarkCommentsAsSeen(String),
+    ImportCalendar { source: String, events: Vec<ImportedEvent> },
+    RemoveImportedCalendar(String),
+    SavePersonalEvent(PersonalEvent),
+    DeletePersonalEvent(String),
+    SetPersonalEventsSync(bool),
+    /// The groups of the user were changed, with the schedule of the new groups
+    GroupsChanged { selection: GroupSelection, events: Vec<RawEvent> },
 
     // Data updating messages sent by the loader in /src/api/generic.rs
     UserInfoSuccess(UserInfo),
@@ -91,6 +137,15 @@ pub enum Msg {
     SurveysSuccess(Vec<Survey>, Vec<SurveyAnswers>),
     ScheduleFailure(ApiError),
     AnnouncementsSuccess(Vec<AnnouncementDesc>),
+    PersonalEventsSuccess(Vec<PersonalEvent>),
+    PushReceived(PushCategory),
+    NotificationsChanged,
+    /// A setting changed, with its name
+    SettingChanged(&'static str),
+    SettingsSyncSuccess(SyncedSettings),
+    /// A push of the synced settings started or ended
+    SettingsSyncChanged,
+    CommentsChecked { eid: String, course: String, comments: Vec<Comment> },
 }
 
 /// The main component of the app.
@@ -102,12 +157,18 @@ pub struct App {
     notifications: Rc<RefCell<LocalNotificationTracker>>,
     friends: Rc<Option<FriendLists>>,
     friends_events: FriendsEvents,
+    imported_calendars: ImportedCalendars,
+    personal_events: PersonalEvents,
     comment_counts: Rc<CommentCounts>,
     seen_comment_counts: Rc<CommentCounts>,
     surveys: Vec<Survey>,
     survey_answers: Vec<SurveyAnswers>,
     tabbar_bait_points: (bool, bool, bool, bool),
+    /// Count and page title last shown by the app badge
+    badge: Option<(usize, &'static str)>,
     page: Page,
+    /// Groups of the schedule loaded by the group editor, until the user info has them
+    groups_schedule: Option<GroupSelection>,
 
     event_closing: bool,
     event_popup_size: Option<usize>,
@@ -128,6 +189,8 @@ impl Component for App {
                 Some("settings") => link2.send_message(Msg::SilentSetPage(Page::Settings)),
                 Some("agenda") => link2.send_message(Msg::SilentSetPage(Page::Agenda)),
                 Some("friends") => link2.send_message(Msg::SilentSetPage(Page::Friends)),
+                Some("free-time") => link2.send_message(Msg::SilentSetPage(Page::FreeTime)),
+                Some("friends-now") => link2.send_message(Msg::SilentSetPage(Page::FriendsNow)),
                 Some("notifications") => link2.send_message(Msg::SilentSetPage(Page::Notifications)),
                 Some(event) if event.starts_with("event/") => {
                     let eid = event[6..].to_string();
@@ -142,11 +205,20 @@ impl Component for App {
         window().add_event_listener_with_callback("popstate", closure.as_ref().unchecked_ref()).unwrap();
         closure.forget();
 
+        // Handle pushes relayed by the service worker
+        listen_to_pushes(ctx.link().clone());
+
+        SETTINGS.apply_all();
+        SETTINGS.subscribe(ctx.link().callback(Msg::SettingChanged));
+        SETTINGS_SYNC.fetch(ctx.link().clone());
+
         // Update data
         let events = CachedData::init(ctx.link().clone()).unwrap_or_default();
         let user_info: Option<UserInfo> = CachedData::init(ctx.link().clone());
         let friends = CachedData::init(ctx.link().clone());
         let friends_events = FriendsEvents::init();
+        let imported_calendars = ImportedCalendars::init();
+        let personal_events = PersonalEvents::init(ctx.link().clone());
         let comment_counts = CachedData::init(ctx.link().clone()).unwrap_or_default();
         let survey_response: SurveyResponse = CachedData::init(ctx.link().clone()).unwrap_or_default();
         let surveys = survey_response.surveys;
@@ -174,6 +246,8 @@ impl Component for App {
         let page = match path.as_str().trim_end_matches('/') {
             "/settings" => Page::Settings,
             "/friends" => Page::Friends,
+            "/free-time" => Page::FreeTime,
+            "/friends-now" => Page::FriendsNow,
             "/notifications" => Page::Notifications,
             event if event.starts_with("/event/") => {
                 let eid = event[7..].to_string();
@@ -232,12 +306,16 @@ impl Component for App {
             notifications: Rc::new(RefCell::new(notifications)),
             friends: Rc::new(friends),
             friends_events,
+            imported_calendars,
+            personal_events,
             comment_counts: Rc::new(comment_counts),
             seen_comment_counts,
             surveys,
             survey_answers,
             tabbar_bait_points,
+            badge: None,
             page,
+            groups_schedule: None,
             event_closing: false,
             event_popup_size: None,
         }
@@ -257,13 +335,18 @@ impl Component for App {
                 }
 
                 friends.save();
+                if let Some(old_friends) = self.friends.as_ref() {
+                    self.notifications.borrow_mut().add_sources(friend_notifications(old_friends, &friends));
+                    self.tabbar_bait_points.2 |= self.notifications.borrow().has_unread();
+                }
+                self.friends_events.sync_with_friends(&friends, ctx.link().clone());
                 self.friends = Rc::new(Some(friends));
                 
-                matches!(self.page, Page::Friends | Page::Event { .. }) || self.tabbar_bait_points.1
+                matches!(self.page, Page::Friends | Page::FreeTime | Page::FriendsNow | Page::Event { .. }) || self.tabbar_bait_points.1
             },
             AppMsg::FriendsEventsSuccess { uid, events } => {
                 self.friends_events.insert(uid, events);
-                matches!(self.page, Page::FriendAgenda { .. } )
+                matches!(self.page, Page::FriendAgenda { .. } | Page::FreeTime | Page::FriendsNow)
             },
             AppMsg::AnnouncementsSuccess(mut announcements) => {
                 // Filter announcements
@@ -299,9 +382,53 @@ impl Component for App {
                 
                 matches!(self.page, Page::Survey { .. }) || self.tabbar_bait_points.2
             },
+            AppMsg::CommentsChecked { eid, course, comments } => {
+                let Some(uid) = self.user_info.deref().as_ref().map(|u| u.uid) else { return false };
+                let sources = CommentWatch::load().update(&eid, &course, &comments, uid);
+                let mut notifications = self.notifications.borrow_mut();
+                notifications.add_sources(sources);
+                let had_bait_point = self.tabbar_bait_points.2;
+                self.tabbar_bait_points.2 = notifications.has_unread();
+                self.tabbar_bait_points.2 != had_bait_point || matches!(self.page, Page::Notifications)
+            },
+            AppMsg::NotificationsChanged => {
+                let has_unread = self.notifications.borrow().has_unread();
+                let changed = self.tabbar_bait_points.2 != has_unread;
+                self.tabbar_bait_points.2 = has_unread;
+                changed
+            },
+            AppMsg::PushReceived(PushCategory::Announcement) => {
+                // Announcements are added to notifications once loaded
+                <Vec<AnnouncementDesc> as CachedData>::refresh(ctx.link().clone());
+                false
+            },
+            AppMsg::SettingChanged(name) => {
+                // Reminders contain the formatted time of the class
+                if matches!(name, "lang" | "hour_cycle" | "time_zone") {
+                    schedule_reminders(Rc::clone(&self.events));
+                }
+                true
+            },
+            AppMsg::SettingsSyncSuccess(values) => {
+                SETTINGS_SYNC.merge(values);
+                SETTINGS_SYNC.push(ctx.link().clone());
+                schedule_reminders(Rc::clone(&self.events));
+                true
+            },
+            AppMsg::SettingsSyncChanged => true,
+            AppMsg::PushReceived(PushCategory::FriendRequest) => {
+                self.tabbar_bait_points.1 = true;
+                <FriendLists as CachedData>::refresh(ctx.link().clone());
+                true
+            },
             AppMsg::ScheduleSuccess(events) => {
+                if !self.events.is_empty() {
+                    self.notifications.borrow_mut().add_sources(schedule_notifications(&self.events, &events));
+                    self.tabbar_bait_points.2 |= self.notifications.borrow().has_unread();
+                }
                 self.events = Rc::new(events);
-                matches!(self.page, Page::Agenda | Page::Event { .. })
+                schedule_reminders(Rc::clone(&self.events));
+                matches!(self.page, Page::Agenda | Page::Event { .. } | Page::FreeTime)
             },
             AppMsg::SaveSurveyAnswer(answers) => {
                 self.survey_answers.retain(|s| s.id != answers.id);
@@ -316,9 +443,16 @@ impl Component for App {
             Msg::UserInfoSuccess(user_info) => {
                 let mut should_refresh = false;
 
+                // The schedule of the groups chosen in the group editor is already loaded once the user info has them
+                let groups = user_info.groups.groups().iter().map(|group| group.to_string()).collect::<Vec<_>>();
+                let groups_schedule_loaded = self.groups_schedule.as_ref().map(|selection| selection.values().all(|value| groups.contains(value))).unwrap_or(false);
+                if groups_schedule_loaded {
+                    self.groups_schedule = None;
+                }
+
                 // Update events if user groups changed
                 if let Some(old_user_info) = self.user_info.as_ref() {
-                    if old_user_info.groups != user_info.groups {
+                    if old_user_info.groups != user_info.groups && !groups_schedule_loaded {
                         self.events = Rc::new(Vec::new());
                         <Vec<RawEvent>>::refresh(ctx.link().clone());
                         should_refresh = true;
@@ -332,10 +466,25 @@ impl Component for App {
                 should_refresh
             },
             Msg::FriendsSuccess(friends) => {
+                if let Some(old_friends) = self.friends.as_ref() {
+                    self.notifications.borrow_mut().add_sources(friend_notifications(old_friends, &friends));
+                    self.tabbar_bait_points.2 |= self.notifications.borrow().has_unread();
+                }
+                self.friends_events.sync_with_friends(&friends, ctx.link().clone());
                 self.friends = Rc::new(Some(friends));
                 matches!(self.page, Page::Friends)
             },
             Msg::CommentCountsSuccess(comment_counts) => {
+                // Look for replies and votes on my comments
+                for (eid, course) in CommentWatch::load().events_to_check(&comment_counts) {
+                    let link = ctx.link().clone();
+                    spawn_local(async move {
+                        match api_get(format!("comments?eid={eid}")).await {
+                            Ok(comments) => link.send_message(AppMsg::CommentsChecked { eid, course, comments }),
+                            Err(e) => link.send_message(AppMsg::ApiFailure(e)),
+                        }
+                    });
+                }
                 self.comment_counts = Rc::new(comment_counts);
                 matches!(self.page, Page::Agenda)
             }
@@ -360,6 +509,14 @@ impl Component for App {
                     _ => (),
                 }
 
+                // Keep the schedules of the friends that are viewed often
+                if let Page::FriendAgenda { pseudo } = &page {
+                    let email = format!("{pseudo}@insa-rouen.fr");
+                    if let Some(f) = self.friends.deref().as_ref().and_then(|f| f.friends.iter().find(|f| f.0.email == *email)) {
+                        self.friends_events.record_use(f.0.uid);
+                    }
+                }
+
                 // Mark notifications as read upon leaving the notifications page
                 if let Page::Notifications = self.page {
                     self.notifications.borrow_mut().mark_all_as_read();
@@ -382,16 +539,20 @@ impl Component for App {
                         }
                     }
                     let app_link = ctx.link().clone();
+                    let animated = !reduced_motion();
                     spawn_local(async move {
-                        window().doc().body().unwrap().set_attribute("style", "overflow: hidden").unwrap();
-                        sleep(Duration::from_millis(500)).await;
-                        window().doc().body().unwrap().remove_attribute("style").unwrap();
+                        if animated {
+                            window().doc().body().unwrap().set_attribute("style", "overflow: hidden").unwrap();
+                            sleep(Duration::from_millis(500)).await;
+                            window().doc().body().unwrap().remove_attribute("style").unwrap();
+                        }
                         if should_mark_as_seen {
                             app_link.send_message(Msg::MarkCommentsAsSeen(eid2));
                         }
                     });
                 }
-                if matches!((&self.page, &page), (Page::Event { .. }, Page::Agenda)) && !self.event_closing {
+                // Let the popup slide out before leaving the event page
+                if matches!((&self.page, &page), (Page::Event { .. }, Page::Agenda)) && !self.event_closing && !reduced_motion() {
                     self.event_closing = true;
                     let link = ctx.link().clone();
                     spawn_local(async move {
@@ -410,6 +571,9 @@ impl Component for App {
                     let _ = history.push_state_with_url(&JsValue::from_str(&data), title, Some(&format!("/{data}")));
                 }
                 document.set_title(title);
+                announce(&page.name());
+                // The title lost the badge count
+                self.badge = None;
                 self.page = page;
                 true
             },
@@ -421,6 +585,40 @@ impl Component for App {
                 crate::COLORS.update_colors(new_colors);
                 true
             },
+            AppMsg::ImportCalendar { source, events } => {
+                self.imported_calendars.insert(&source, events);
+                matches!(self.page, Page::Settings | Page::Agenda)
+            }
+            AppMsg::RemoveImportedCalendar(source) => {
+                self.imported_calendars.remove(&source);
+                matches!(self.page, Page::Settings | Page::Agenda)
+            }
+            AppMsg::SavePersonalEvent(event) => {
+                self.personal_events.upsert(event);
+                matches!(self.page, Page::Agenda | Page::Event { .. })
+            }
+            AppMsg::DeletePersonalEvent(id) => {
+                self.personal_events.remove(&id);
+                matches!(self.page, Page::Agenda | Page::Event { .. })
+            }
+            AppMsg::GroupsChanged { selection, mut events } => {
+                // The schedule is already the one of the new groups, so it isn't fetched again with the user info
+                events.sort_by_key(|e| e.start_unixtime);
+                events.save();
+                self.events = Rc::new(events);
+                schedule_reminders(Rc::clone(&self.events));
+                self.groups_schedule = Some(selection);
+                <UserInfo as CachedData>::refresh(ctx.link().clone());
+                true
+            }
+            AppMsg::SetPersonalEventsSync(enabled) => {
+                self.personal_events.set_sync_enabled(enabled, ctx.link().clone());
+                false
+            }
+            AppMsg::PersonalEventsSuccess(events) => {
+                self.personal_events.merge(events);
+                matches!(self.page, Page::Agenda | Page::Event { .. })
+            }
             AppMsg::MarkCommentsAsSeen(eid) => {
                 let val = self.comment_counts.get(&eid).copied().unwrap_or_default();
                 let mut seen_comment_counts = self.seen_comment_counts.deref().clone();
@@ -436,6 +634,14 @@ impl Component for App {
     
     fn rendered(&mut self, _ctx: &Context<Self>, _first_render: bool) {
         crate::colors::COLORS_CHANGED.store(false, std::sync::atomic::Ordering::Relaxed);
+
+        // Bait points changes always trigger a render, so the badge is kept up to date here
+        let count = badge_count(&self.notifications.borrow(), &self.friends, &self.events, &self.comment_counts, &self.seen_comment_counts);
+        let title = self.page.data_and_title().1;
+        if self.badge != Some((count, title)) {
+            set_badge(count, title);
+            self.badge = Some((count, title));
+        }
     }
     
     fn view(&self, ctx: &Context<Self>) -> Html {
@@ -443,11 +649,14 @@ impl Component for App {
             Page::Agenda => html!(<>
                 <Agenda
                     events={Rc::clone(&self.events)}
+                    imported_events={self.imported_calendars.events()}
+                    personal_events={self.personal_events.events()}
                     app_link={ctx.link().clone()}
                     user_info={Rc::clone(&self.user_info)}
                     friends={Rc::clone(&self.friends)}
                     comment_counts={Rc::clone(&self.comment_counts)}
-                    seen_comment_counts={Rc::clone(&self.seen_comment_counts)} />
+                    seen_comment_counts={Rc::clone(&self.seen_comment_counts)}
+                    syncing={SETTINGS_SYNC.has_pending()} />
                 <TabBar app_link={ctx.link()} page={self.page.clone()} bait_points={self.tabbar_bait_points} />
             </>),
             Page::Event { eid } => {
@@ -461,12 +670,15 @@ impl Component for App {
                 html!(<>
                     <Agenda
                         events={Rc::clone(&self.events)}
+                        imported_events={self.imported_calendars.events()}
+                        personal_events={self.personal_events.events()}
                         app_link={ctx.link().clone()}
                         popup={Some((event, self.event_closing, self.event_popup_size.to_owned()))}
                         friends={Rc::clone(&self.friends)}
                         user_info={Rc::clone(&self.user_info)}
                         comment_counts={Rc::clone(&self.comment_counts)}
-                        seen_comment_counts={Rc::clone(&self.seen_comment_counts)} />
+                        seen_comment_counts={Rc::clone(&self.seen_comment_counts)}
+                        syncing={SETTINGS_SYNC.has_pending()} />
                     <TabBar app_link={ctx.link()} page={self.page.clone()} bait_points={self.tabbar_bait_points} />
                 </>)
             },
@@ -481,10 +693,13 @@ impl Component for App {
                     None => return html!("404 friend not found"), // TODO 404 page
                 };
                 let events = self.friends_events.get_events(uid, ctx.link().clone()).unwrap_or_default();
+                let refreshing = self.friends_events.is_refreshing(uid);
                 let profile_src = format!("https://api.dicebear.com/5.x/identicon/svg?seed={}", uid);
                 html!(<>
                     <Agenda
                         events={events}
+                        compared_events={Some(Rc::clone(&self.events))}
+                        refreshing={refreshing}
                         app_link={ctx.link().clone()}
                         profile_src={profile_src}
                         friends={Rc::clone(&self.friends)}
@@ -494,12 +709,27 @@ impl Component for App {
                     <TabBar app_link={ctx.link()} page={self.page.clone()} bait_points={self.tabbar_bait_points} />
                 </>)
             },
+            Page::FreeTime => html!(<>
+                <FreeTimePage
+                    app_link={ctx.link().clone()}
+                    events={Rc::clone(&self.events)}
+                    friends={Rc::clone(&self.friends)}
+                    friends_events={self.friends_events.clone()} />
+                <TabBar app_link={ctx.link()} page={self.page.clone()} bait_points={self.tabbar_bait_points} />
+            </>),
+            Page::FriendsNow => html!(<>
+                <FriendsNowPage
+                    app_link={ctx.link().clone()}
+                    friends={Rc::clone(&self.friends)}
+                    friends_events={self.friends_events.clone()} />
+                <TabBar app_link={ctx.link()} page={self.page.clone()} bait_points={self.tabbar_bait_points} />
+            </>),
             Page::Notifications => html!(<>
-                <NotificationsPage notifications={Rc::clone(&self.notifications)} />
+                <NotificationsPage app_link={ctx.link().clone()} notifications={Rc::clone(&self.notifications)} />
                 <TabBar app_link={ctx.link()} page={self.page.clone()} bait_points={self.tabbar_bait_points} />
             </>),
             Page::Settings => html!(<>
-                <SettingsPage app_link={ ctx.link().clone() } user_info={Rc::clone(&self.user_info)} />
+                <SettingsPage app_link={ ctx.link().clone() } user_info={Rc::clone(&self.user_info)} events={Rc::clone(&self.events)} imported_sources={self.imported_calendars.sources()} syncing={SETTINGS_SYNC.has_pending()} />
                 <TabBar app_link={ctx.link()} page={self.page.clone()} bait_points={self.tabbar_bait_points} />
             </>),
             Page::Survey { sid } => {
@@ -1,8 +1,23 @@
 pub use crate::prelude::*;
 
+/// Notifications older than this number of seconds are forgotten
+const RETENTION: u64 = 60 * 86400;
+
+/// Maximum number of stored notifications
+const MAX_NOTIFICATIONS: usize = 50;
+
+/// Maximum number of remembered dismissed notifications
+const MAX_DISMISSED: usize = 200;
+
 #[derive(Serialize, Deserialize)]
 pub struct LocalNotificationTracker {
     pub(self) notifications: Vec<(String, bool, NotificationSource)>,
+    /// Ids of the notifications the user removed, so that they don't come back
+    #[serde(default)]
+    dismissed: Vec<String>,
+    /// Notifications marked as unread during the current visit of the page, that must stay unread when leaving it
+    #[serde(skip)]
+    kept_unread: Vec<String>,
 }
 
 impl LocalNotificationTracker {
@@ -13,9 +28,13 @@ impl LocalNotificationTracker {
     }
 
     pub fn load() -> Self {
-        Self::try_load().unwrap_or_else(|| Self {
+        let mut tracker = Self::try_load().unwrap_or_else(|| Self {
             notifications: Vec::new(),
-        })
+            dismissed: Vec::new(),
+            kept_unread: Vec::new(),
+        });
+        tracker.prune(now() as u64);
+        tracker
     }
 
     fn save(&self) {
@@ -24,50 +43,242 @@ impl LocalNotificationTracker {
         local_storage.set("notification_tracker", &announcement_tracker).unwrap();
     }
 
+    /// Remove expired notifications, then the oldest ones if there are too many, read ones first
+    fn prune(&mut self, now: u64) {
+        self.notifications.retain(|(_, _, source)| !source.is_expired(now) && source.ts() + RETENTION > now);
+        while self.notifications.len() > MAX_NOTIFICATIONS {
+            // Notifications are sorted from the most recent to the oldest
+            let index = self.notifications.iter().rposition(|(_, seen, _)| *seen).unwrap_or(self.notifications.len() - 1);
+            self.notifications.remove(index);
+        }
+        if self.dismissed.len() > MAX_DISMISSED {
+            self.dismissed.drain(..self.dismissed.len() - MAX_DISMISSED);
+        }
+    }
+
+    fn add(&mut self, id: String, source: NotificationSource) {
+        let now = now() as u64;
+        if source.is_expired(now) || source.ts() + RETENTION <= now || self.dismissed.contains(&id) {
+            return;
+        }
+        if !self.notifications.iter().any(|(i,_,_)| i == &id) {
+            self.notifications.push((id, false, source));
+        }
+    }
+
     pub fn add_announcements(&mut self, announcements: &[AnnouncementDesc]) {
         for announcement in announcements {
-            let id = format!("announcement:{}", announcement.id);
-            if !self.notifications.iter().any(|(i,_,_)| i == &id) {
-                self.notifications.push((id, false, NotificationSource::Announcement(announcement.clone())));
-            }
+            self.add(format!("announcement:{}", announcement.id), NotificationSource::Announcement(announcement.clone()));
         }
         self.notifications.sort_by_key(|(_,_,n)| u64::MAX - n.ts());
+        self.prune(now() as u64);
         self.save()
     }
 
     pub fn add_surveys(&mut self, surveys: &[Survey]) {
         for survey in surveys {
-            let id = format!("survey:{}", survey.id);
-            if !self.notifications.iter().any(|(i,_,_)| i == &id) {
-                self.notifications.push((id, false, NotificationSource::Survey(survey.clone())));
-            }
+            self.add(format!("survey:{}", survey.id), NotificationSource::Survey(survey.clone()));
         }
         self.notifications.sort_by_key(|(_,_,n)| u64::MAX - n.ts());
+        self.prune(now() as u64);
         self.save()
     }
 
-    pub fn unseen(&self) -> impl Iterator<Item = &NotificationSource> {
-        self.notifications.iter().filter(|(_,seen, _)| !seen).map(|(_,_,source)| source)
+    /// Add notifications from other sources, identified by a unique id
+    pub fn add_sources(&mut self, sources: Vec<(String, NotificationSource)>) {
+        if sources.is_empty() {
+            return;
+        }
+        for (id, source) in sources {
+            self.add(id, source);
+        }
+        self.notifications.sort_by_key(|(_,_,n)| u64::MAX - n.ts());
+        self.prune(now() as u64);
+        self.save()
     }
 
-    pub fn seen(&self) -> impl Iterator<Item = &NotificationSource> {
-        self.notifications.iter().filter(|(_,seen, _)| *seen).map(|(_,_,source)| source)
+    /// Mark all notifications as read, except the ones that were explicitly marked as unread
+    pub fn mark_all_as_read(&mut self) {
+        let kept_unread = std::mem::take(&mut self.kept_unread);
+        self.notifications.iter_mut().filter(|(id,_,_)| !kept_unread.contains(id)).for_each(|(_,seen, _)| *seen = true);
+        self.save()
     }
 
-    pub fn mark_all_as_read(&mut self) {
-        self.notifications.iter_mut().for_each(|(_,seen, _)| *seen = true);
+    pub fn set_read(&mut self, id: &str, read: bool) {
+        if let Some((_, seen, _)) = self.notifications.iter_mut().find(|(i,_,_)| i == id) {
+            *seen = read;
+        }
+        self.kept_unread.retain(|i| i != id);
+        if !read {
+            self.kept_unread.push(id.to_string());
+        }
+        self.save()
+    }
+
+    pub fn dismiss(&mut self, id: &str) {
+        self.notifications.retain(|(i,_,_)| i != id);
+        self.kept_unread.retain(|i| i != id);
+        self.dismissed.push(id.to_string());
+        self.prune(now() as u64);
         self.save()
     }
 
     pub fn has_unread(&self) -> bool {
         self.notifications.iter().any(|(_,seen, _)| !seen)
     }
+
+    pub fn unread_count(&self) -> usize {
+        self.notifications.iter().filter(|(_,seen, _)| !seen).count()
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub enum ScheduleChangeKind {
+    Added,
+    Removed,
+    Moved,
+    Relocated,
 }
 
 #[derive(Serialize, Deserialize)]
 pub enum NotificationSource {
     Announcement(AnnouncementDesc),
     Survey(Survey),
+    FriendRequest { uid: i64, name: String, ts: u64 },
+    FriendAccepted { uid: i64, name: String, ts: u64 },
+    CommentReply { eid: String, course: String, author: String, ts: u64 },
+    CommentVotes { eid: String, course: String, score: i64, ts: u64 },
+    ScheduleChange { eid: String, course: String, kind: ScheduleChangeKind, start_unixtime: u64, ts: u64 },
+}
+
+/// Notifications about friend requests received or accepted since the previous friend lists
+pub fn friend_notifications(old: &FriendLists, new: &FriendLists) -> Vec<(String, NotificationSource)> {
+    let ts = now() as u64;
+    let mut sources = Vec::new();
+    for request in &new.incoming {
+        let user = &request.from.0;
+        if !old.incoming.iter().any(|r| r.from.0.uid == user.uid) {
+            let name = user.email.trim_end_matches("@insa-rouen.fr").to_string();
+            sources.push((format!("friend-request:{}:{ts}", user.uid), NotificationSource::FriendRequest { uid: user.uid, name, ts }));
+        }
+    }
+    for request in &old.outgoing {
+        let user = &request.to.0;
+        if new.friends.iter().any(|(friend, _)| friend.uid == user.uid) {
+            let name = user.email.trim_end_matches("@insa-rouen.fr").to_string();
+            sources.push((format!("friend-accepted:{}", user.uid), NotificationSource::FriendAccepted { uid: user.uid, name, ts }));
+        }
+    }
+    sources
+}
+
+/// Changes are only reported for classes starting within this number of seconds
+const SCHEDULE_CHANGE_HORIZON: u64 = 14 * 86400;
+
+/// Notifications about the upcoming classes that changed between two versions of the schedule
+pub fn schedule_notifications(old: &[RawEvent], new: &[RawEvent]) -> Vec<(String, NotificationSource)> {
+    let ts = now() as u64;
+    let upcoming = |e: &&RawEvent| e.start_unixtime > ts && e.start_unixtime < ts + SCHEDULE_CHANGE_HORIZON;
+    let mut sources = Vec::new();
+    let mut push = |event: &RawEvent, kind: ScheduleChangeKind| {
+        let id = format!("schedule:{}:{}:{ts}", event.eid, kind as u8);
+        sources.push((id, NotificationSource::ScheduleChange { eid: event.eid.clone(), course: event.format_name(), kind, start_unixtime: event.start_unixtime, ts }));
+    };
+    for event in new.iter().filter(upcoming) {
+        match old.iter().find(|e| e.eid == event.eid) {
+            None => push(event, ScheduleChangeKind::Added),
+            Some(old_event) if old_event.start_unixtime != event.start_unixtime || old_event.end_unixtime != event.end_unixtime => push(event, ScheduleChangeKind::Moved),
+            Some(old_event) if old_event.location != event.location => push(event, ScheduleChangeKind::Relocated),
+            Some(_) => (),
+        }
+    }
+    for event in old.iter().filter(upcoming) {
+        if !new.iter().any(|e| e.eid == event.eid) {
+            push(event, ScheduleChangeKind::Removed);
+        }
+    }
+    sources
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct WatchedEvent {
+    course: String,
+    comment_count: usize,
+    last_checked: u64,
+    /// Number of replies and score of each of my comments
+    comments: HashMap<u64, (usize, i64)>,
+}
+
+/// Keeps track of my comments so that replies and votes on them can be notified
+#[derive(Default, Serialize, Deserialize)]
+pub struct CommentWatch {
+    events: HashMap<String, WatchedEvent>,
+}
+
+/// Watched events are checked again after this number of seconds even if their comment count didn't change, to detect votes
+const COMMENT_WATCH_INTERVAL: u64 = 6 * 3600;
+
+/// Maximum number of events checked at once
+const MAX_COMMENT_CHECKS: usize = 5;
+
+impl CommentWatch {
+    pub fn load() -> Self {
+        let local_storage = window().local_storage().unwrap().unwrap();
+        let Ok(Some(watch)) = local_storage.get("comment_watch") else { return Self::default() };
+        serde_json::from_str(&watch).unwrap_or_default()
+    }
+
+    fn save(&self) {
+        let local_storage = window().local_storage().unwrap().unwrap();
+        local_storage.set("comment_watch", &serde_json::to_string(self).unwrap()).unwrap();
+    }
+
+    /// Update the state of an event from its comments, returning notifications for what changed on my comments
+    pub fn update(&mut self, eid: &str, course: &str, comments: &[Comment], uid: i64) -> Vec<(String, NotificationSource)> {
+        let ts = now() as u64;
+        let mut sources = Vec::new();
+        let mine = comments.iter().filter(|c| c.author.uid == uid).map(|c| {
+            let replies = comments.iter().filter(|r| r.parent == Some(c.cid)).collect::<Vec<_>>();
+            let score = c.upvotes as i64 - c.downvotes as i64;
+            (c.cid, replies, score)
+        }).collect::<Vec<_>>();
+        if mine.is_empty() && !self.events.contains_key(eid) {
+            return sources;
+        }
+
+        let watched = self.events.entry(eid.to_string()).or_default();
+        for (cid, replies, score) in &mine {
+            if let Some((old_replies, old_score)) = watched.comments.get(cid) {
+                for reply in replies.iter().filter(|r| r.author.uid != uid).skip(*old_replies) {
+                    let author = reply.author.get_username();
+                    sources.push((format!("comment-reply:{eid}:{}", reply.cid), NotificationSource::CommentReply { eid: eid.to_string(), course: course.to_string(), author, ts }));
+                }
+                if score != old_score {
+                    sources.push((format!("comment-votes:{eid}:{cid}:{score}"), NotificationSource::CommentVotes { eid: eid.to_string(), course: course.to_string(), score: *score, ts }));
+                }
+            }
+        }
+        watched.course = course.to_string();
+        watched.comment_count = comments.len();
+        watched.last_checked = ts;
+        watched.comments = mine.into_iter().map(|(cid, replies, score)| (cid, (replies.iter().filter(|r| r.author.uid != uid).count(), score))).collect();
+        if watched.comments.is_empty() {
+            self.events.remove(eid);
+        }
+        self.save();
+        sources
+    }
+
+    /// Events whose comments should be fetched again to look for replies and votes
+    pub fn events_to_check(&self, comment_counts: &CommentCounts) -> Vec<(String, String)> {
+        let now = now() as u64;
+        let mut events = self.events.iter()
+            .filter(|(eid, watched)| comment_counts.get(*eid).copied().unwrap_or_default() != watched.comment_count || watched.last_checked + COMMENT_WATCH_INTERVAL < now)
+            .map(|(eid, watched)| (eid.clone(), watched.course.clone()))
+            .collect::<Vec<_>>();
+        events.truncate(MAX_COMMENT_CHECKS);
+        events
+    }
 }
 
 impl NotificationSource {
@@ -75,6 +286,20 @@ impl NotificationSource {
         match self {
             NotificationSource::Announcement(announcement) => announcement.start_ts,
             NotificationSource::Survey(survey) => survey.start_ts as u64,
+            NotificationSource::FriendRequest { ts, .. }
+            | NotificationSource::FriendAccepted { ts, .. }
+            | NotificationSource::CommentReply { ts, .. }
+            | NotificationSource::CommentVotes { ts, .. }
+            | NotificationSource::ScheduleChange { ts, .. } => *ts,
+        }
+    }
+
+    /// Whether the notification is no longer relevant
+    fn is_expired(&self, now: u64) -> bool {
+        match self {
+            NotificationSource::Announcement(announcement) => announcement.end_ts < now,
+            NotificationSource::ScheduleChange { start_unixtime, .. } => *start_unixtime < now,
+            _ => false,
         }
     }
 
@@ -82,55 +307,156 @@ impl NotificationSource {
         match self {
             NotificationSource::Announcement(announcement) => {
                 let mut text = HashMap::new();
-                if announcement.ty == ContentType::Text {
-                    if let Some(content_fr) = &announcement.content_fr {
-                        text.insert(String::from("fr"), content_fr.clone());
-                    }
-                    if let Some(content_en) = &announcement.content_en {
-                        text.insert(String::from("en"), content_en.clone());
+                let mut html = HashMap::new();
+                let mut button_target = None;
+                for (lang, content) in [("fr", &announcement.content_fr), ("en", &announcement.content_en)] {
+                    let Some(content) = content else { continue };
+                    match announcement.ty {
+                        ContentType::Text => {
+                            text.insert(String::from(lang), content.clone());
+                        },
+                        ContentType::Html => {
+                            html.insert(String::from(lang), sanitize_html(content));
+                        },
+                        ContentType::Markdown => {
+                            html.insert(String::from(lang), markdown_to_html(content));
+                        },
+                        ContentType::Image => {
+                            let alt = if lang == "fr" { "Image de l'annonce" } else { "Announcement image" };
+                            if let Some(image) = image_html(content, alt) {
+                                html.insert(String::from(lang), image);
+                            }
+                        },
+                        ContentType::Link => {
+                            if let Some(url) = safe_url(content) {
+                                if button_target.is_none() || lang == SETTINGS.locale() {
+                                    button_target = Some((url, t("Ouvrir").to_string()));
+                                }
+                            }
+                        },
                     }
                 }
+                html.retain(|_, html| has_content(html));
+                if text.is_empty() && html.is_empty() {
+                    text = localized(t("Une nouvelle annonce a été publiée.").to_string());
+                }
                 Notification {
                     text,
+                    html,
                     image_src: String::from("/agenda/images/info.svg"),
                     image_alt: String::from("Information"),
                     ts: announcement.start_ts,
-                    button_target: None,
+                    button_target,
                 }
             },
             NotificationSource::Survey(survey) => {
-                let mut text = HashMap::new();
-                text.insert(String::from("fr"), format!("Un nouveau sondage a été publié: {}", survey.title));
-                text.insert(String::from("en"), format!("A new survey has been published: {}", survey.title));
+                let text = localized(tf("Un nouveau sondage a été publié : {title}", &[("title", &survey.title)]));
                 let mut button_target = None;
                 if survey.start_ts as u64 <= now && survey.end_ts as u64 >= now {
-                    button_target = Some((format!("/survey/{}", survey.id), String::from("Participer")));
+                    button_target = Some((format!("/survey/{}", survey.id), t("Participer").to_string()));
                 }
                 Notification {
                     text,
+                    html: HashMap::new(),
                     image_src: String::from("/agenda/images/survey.svg"),
                     image_alt: String::from("Survey"),
                     ts: survey.start_ts as u64,
                     button_target,
                 }
             },
+            NotificationSource::FriendRequest { name, ts, .. } => Notification {
+                text: localized(tf("{name} vous a envoyé une demande d'ami.", &[("name", &name)])),
+                html: HashMap::new(),
+                image_src: String::from("/agenda/images/people.svg"),
+                image_alt: String::from("Friends"),
+                ts: *ts,
+                button_target: Some((String::from("/friends"), t("Répondre").to_string())),
+            },
+            NotificationSource::FriendAccepted { name, ts, .. } => Notification {
+                text: localized(tf("{name} a accepté votre demande d'ami.", &[("name", &name)])),
+                html: HashMap::new(),
+                image_src: String::from("/agenda/images/people.svg"),
+                image_alt: String::from("Friends"),
+                ts: *ts,
+                button_target: Some((format!("/friend-agenda/{name}"), t("Voir l'agenda").to_string())),
+            },
+            NotificationSource::CommentReply { eid, course, author, ts } => Notification {
+                text: localized(tf("{author} a répondu à votre commentaire sur {course}.", &[("author", &author), ("course", &course)])),
+                html: HashMap::new(),
+                image_src: String::from("/agenda/images/bell.svg"),
+                image_alt: String::from("Comment"),
+                ts: *ts,
+                button_target: Some((format!("/event/{eid}"), t("Voir").to_string())),
+            },
+            NotificationSource::CommentVotes { eid, course, score, ts } => Notification {
+                text: localized(tf("Votre commentaire sur {course} a maintenant un score de {score}.", &[("course", &course), ("score", &score)])),
+                html: HashMap::new(),
+                image_src: String::from("/agenda/images/bell.svg"),
+                image_alt: String::from("Comment"),
+                ts: *ts,
+                button_target: Some((format!("/event/{eid}"), t("Voir").to_string())),
+            },
+            NotificationSource::ScheduleChange { eid, course, kind, start_unixtime, ts } => {
+                let start = user_tz().timestamp_opt(*start_unixtime as i64, 0).unwrap();
+                let date = tf("{day} à {time}", &[("day", &format_day(start.weekday(), start.day())), ("time", &format_time(start))]);
+                let text = match kind {
+                    ScheduleChangeKind::Added => localized(tf("Nouveau cours : {course} le {date}.", &[("course", &course), ("date", &date)])),
+                    ScheduleChangeKind::Removed => localized(tf("Le cours {course} du {date} a été supprimé.", &[("course", &course), ("date", &date)])),
+                    ScheduleChangeKind::Moved => localized(tf("Le cours {course} a été déplacé au {date}.", &[("course", &course), ("date", &date)])),
+                    ScheduleChangeKind::Relocated => localized(tf("Le cours {course} du {date} a changé de salle.", &[("course", &course), ("date", &date)])),
+                };
+                let button_target = match kind {
+                    ScheduleChangeKind::Removed => None,
+                    _ => Some((format!("/event/{eid}"), t("Voir").to_string())),
+                };
+                Notification {
+                    text,
+                    html: HashMap::new(),
+                    image_src: String::from("/agenda/images/calendar-btn.svg"),
+                    image_alt: String::from("Schedule"),
+                    ts: *ts,
+                    button_target,
+                }
+            },
         }
     }
 }
 
+/// Text of a notification generated by the app, which is already translated to the language of the user
+fn localized(text: String) -> HashMap<String, String> {
+    let mut texts = HashMap::new();
+    texts.insert(SETTINGS.locale().to_string(), text);
+    texts
+}
+
+/// The entry in the language of the user, falling back to English and then to any available language
+fn localized_entry(entries: &HashMap<String, String>) -> Option<&String> {
+    entries.get(SETTINGS.locale()).or_else(|| entries.get("en")).or_else(|| entries.values().next())
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Notification {
     text: HashMap<String, String>,
+    /// Sanitized HTML of formatted announcements, displayed instead of the text
+    #[serde(default)]
+    html: HashMap<String, String>,
     image_src: String,
     image_alt: String,
     ts: u64,
     button_target: Option<(String, String)>,
 }
 
-pub struct NotificationsPage;
+/// Horizontal distance after which a swiped notification is dismissed, in pixels
+const SWIPE_THRESHOLD: i32 = 100;
+
+pub struct NotificationsPage {
+    /// Notification being swiped, with the start and current horizontal positions of the touch
+    swiping: Option<(String, i32, i32)>,
+}
 
 #[derive(Clone, Properties)]
 pub struct NotificationsProps {
+    pub app_link: AppLink,
     pub notifications: Rc<RefCell<LocalNotificationTracker>>,
 }
 
@@ -139,6 +465,17 @@ impl PartialEq for NotificationsProps {
 }
 
 pub enum NotificationsMsg {
+    ToggleRead(MouseEvent),
+    Dismiss(MouseEvent),
+    TouchStart(TouchEvent),
+    TouchMove(TouchEvent),
+    TouchEnd,
+}
+
+/// Get the id of the notification containing the target of an event
+fn notification_id(event: &web_sys::Event) -> Option<String> {
+    let el = event.target()?.dyn_into::<web_sys::Element>().ok()?;
+    el.closest("[data-id]").ok()??.get_attribute("data-id")
 }
 
 impl Component for NotificationsPage {
@@ -146,26 +483,77 @@ impl Component for NotificationsPage {
     type Properties = NotificationsProps;
 
     fn create(_ctx: &Context<Self>) -> Self {
-        Self
+        Self { swiping: None }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            NotificationsMsg::ToggleRead(event) => {
+                let Some(id) = notification_id(&event) else { return false };
+                let mut notifications = ctx.props().notifications.borrow_mut();
+                let read = notifications.notifications.iter().find(|(i,_,_)| *i == id).map(|(_,seen,_)| !seen).unwrap_or(true);
+                notifications.set_read(&id, read);
+            }
+            NotificationsMsg::Dismiss(event) => {
+                let Some(id) = notification_id(&event) else { return false };
+                ctx.props().notifications.borrow_mut().dismiss(&id);
+            }
+            NotificationsMsg::TouchStart(event) => {
+                let (Some(id), Some(touch)) = (notification_id(&event), event.touches().get(0)) else { return false };
+                self.swiping = Some((id, touch.client_x(), touch.client_x()));
+                return false;
+            }
+            NotificationsMsg::TouchMove(event) => {
+                let (Some((_, _, x)), Some(touch)) = (&mut self.swiping, event.touches().get(0)) else { return false };
+                *x = touch.client_x();
+                // Only the swiped notification moves, the app doesn't need to know yet
+                return true;
+            }
+            NotificationsMsg::TouchEnd => {
+                let Some((id, start_x, x)) = self.swiping.take() else { return false };
+                if (x - start_x).abs() <= SWIPE_THRESHOLD {
+                    return true;
+                }
+                ctx.props().notifications.borrow_mut().dismiss(&id);
+            }
+        }
+        ctx.props().app_link.send_message(AppMsg::NotificationsChanged);
+        true
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
         let now = now() as u64;
         
-        let unseen = ctx.props().notifications.borrow().unseen().map(|source| source.to_notification(now)).collect::<Vec<_>>();
-        let unseen_text_iter = unseen.iter().map(|n| n.text.get("fr").unwrap_or(&String::from("")).clone());
-        let unseen_src_iter = unseen.iter().map(|n| n.image_src.clone());
-        let unseen_alt_iter = unseen.iter().map(|n| n.image_alt.clone());
-        let unseen_button_iter = unseen.iter().map(|n| n.button_target.as_ref().map(|(uri,text)| html!(<a class="friends-agenda-button" href={uri.to_owned()}>{text}</a>)));
-
-        let seen = ctx.props().notifications.borrow().seen().map(|source| source.to_notification(now)).collect::<Vec<_>>();
-        let text_iter = seen.iter().map(|n| n.text.get("fr").unwrap_or(&String::from("")).clone());
-        let src_iter = seen.iter().map(|n| n.image_src.clone());
-        let alt_iter = seen.iter().map(|n| n.image_alt.clone());
-        let button_iter = seen.iter().map(|n| n.button_target.as_ref().map(|(uri,text)| html!(<a class="friends-agenda-button" href={uri.to_owned()}>{text}</a>)));
+        let notifications = ctx.props().notifications.borrow();
+        let entries = notifications.notifications.iter().map(|(id, seen, source)| (id.clone(), *seen, source.to_notification(now))).collect::<Vec<_>>();
+        let id_iter = entries.iter().map(|(id, _, _)| id.clone());
+        let class_iter = entries.iter().map(|(_, seen, _)| if *seen { "notification" } else { "unseen-notification notification" });
+        let style_iter = entries.iter().map(|(id, _, _)| match &self.swiping {
+            Some((swiped_id, start_x, x)) if swiped_id == id => format!("transform: translateX({}px); opacity: {};", x - start_x, 1.0 - ((x - start_x).abs() as f64 / 300.0).min(0.8)),
+            _ => String::new(),
+        });
+        let content_iter = entries.iter().map(|(_, _, n)| match localized_entry(&n.html) {
+            Some(html) => yew::virtual_dom::VNode::from_html_unchecked(AttrValue::from(format!("<div class=\"notification-content\">{html}</div>"))),
+            None => html!(<p>{localized_entry(&n.text).cloned().unwrap_or_default()}</p>),
+        });
+        let time_iter = entries.iter().map(|(_, _, n)| format_time_diff(now.saturating_sub(n.ts) as i64));
+        let src_iter = entries.iter().map(|(_, _, n)| n.image_src.clone());
+        let alt_iter = entries.iter().map(|(_, _, n)| n.image_alt.clone());
+        let button_iter = entries.iter().map(|(_, _, n)| n.button_target.as_ref().map(|(uri,text)| html!(<a class="friends-agenda-button" href={uri.to_owned()}>{text}</a>)));
+        let read_label_iter = entries.iter().map(|(_, seen, _)| if *seen { t("Marquer comme non lu") } else { t("Marquer comme lu") });
 
-        let notifications_empty = unseen.is_empty() && seen.is_empty();
+        let notifications_empty = entries.is_empty();
 
-        template_html!("src/notifications/notifications.html", ...)
+        template_html!(
+            "src/notifications/notifications.html",
+            onclick_toggle_read = {ctx.link().callback(NotificationsMsg::ToggleRead)},
+            onclick_dismiss = {ctx.link().callback(NotificationsMsg::Dismiss)},
+            delete_label = {t("Supprimer")},
+            empty_message = {t("Vous n'avez aucune notification (pour l'instant !)")},
+            ontouchstart = {ctx.link().callback(NotificationsMsg::TouchStart)},
+            ontouchmove = {ctx.link().callback(NotificationsMsg::TouchMove)},
+            ontouchend = {ctx.link().callback(|_| NotificationsMsg::TouchEnd)},
+            ...
+        )
     }
 }
@@ -0,0 +1,313 @@
+use crate::prelude::*;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum Recurrence {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+/// An event created by the user in the app (study session, appointment...).
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct PersonalEvent {
+    pub id: String,
+    pub title: String,
+    pub start_unixtime: u64,
+    pub end_unixtime: u64,
+    pub color: String,
+    pub recurrence: Option<Recurrence>,
+    /// Last day (inclusive) on which a recurring event occurs
+    pub recurrence_end: Option<NaiveDate>,
+    pub notes: String,
+    pub last_modified: i64,
+    /// Deleted events are kept so that the deletion can be synced
+    #[serde(default)]
+    pub deleted: bool,
+}
+
+impl PersonalEvent {
+    /// A new one hour event starting at the given time
+    pub fn new(start_unixtime: u64) -> Self {
+        let mut id = format!("{:x}", now());
+        let crypto = window().crypto().unwrap();
+        let mut random_bytes = [0; 8];
+        crypto.get_random_values_with_u8_array(&mut random_bytes).unwrap();
+        for byte in random_bytes {
+            id.push_str(&format!("{byte:02x}"));
+        }
+
+        PersonalEvent {
+            id,
+            title: String::new(),
+            start_unixtime,
+            end_unixtime: start_unixtime + 3600,
+            color: String::from("#3A86FF"),
+            recurrence: None,
+            recurrence_end: None,
+            notes: String::new(),
+            last_modified: now(),
+            deleted: false,
+        }
+    }
+
+    pub fn duration(&self) -> u64 {
+        self.end_unixtime.saturating_sub(self.start_unixtime)
+    }
+
+    /// Returns the start of the occurrence happening on the given day, if any
+    pub fn occurs_on(&self, date: NaiveDate) -> Option<u64> {
+        if self.deleted {
+            return None;
+        }
+        let start = user_tz().timestamp_opt(self.start_unixtime as i64, 0).unwrap();
+        let start_date = start.date_naive();
+        if date < start_date || self.recurrence_end.map(|end| date > end).unwrap_or(false) {
+            return None;
+        }
+        let occurs = match self.recurrence {
+            None => date == start_date,
+            Some(Recurrence::Daily) => true,
+            Some(Recurrence::Weekly) => (date - start_date).num_days() % 7 == 0,
+            Some(Recurrence::Monthly) => date.day() == start_date.day(),
+        };
+        if !occurs {
+            return None;
+        }
+        user_tz().from_local_datetime(&date.and_time(start.time())).earliest().map(|dt| dt.timestamp() as u64)
+    }
+}
+
+/// Personal events are stored locally, and optionally synced with the server.
+#[derive(Default, Clone)]
+pub struct PersonalEvents {
+    events: Rc<Vec<PersonalEvent>>,
+}
+
+impl PersonalEvents {
+    pub fn init(app_link: AppLink) -> Self {
+        if Self::sync_enabled() {
+            Self::fetch(app_link);
+        }
+
+        let local_storage = window().local_storage().unwrap().unwrap();
+        let Ok(Some(cached_str)) = local_storage.get("personal_events") else { return Self::default() };
+        let Ok(events) = serde_json::from_str::<Vec<PersonalEvent>>(&cached_str) else { return Self::default() };
+
+        Self { events: Rc::new(events) }
+    }
+
+    fn save(&self) {
+        let local_storage = window().local_storage().unwrap().unwrap();
+        local_storage.set("personal_events", &serde_json::to_string(self.events.as_ref()).unwrap()).unwrap();
+    }
+
+    /// All events, including deleted ones
+    pub fn events(&self) -> Rc<Vec<PersonalEvent>> {
+        Rc::clone(&self.events)
+    }
+
+    /// Create or update an event
+    pub fn upsert(&mut self, mut event: PersonalEvent) {
+        event.last_modified = now();
+        let mut events = self.events.as_ref().clone();
+        events.retain(|e| e.id != event.id);
+        events.push(event);
+        self.events = Rc::new(events);
+        self.save();
+        self.push();
+    }
+
+    pub fn remove(&mut self, id: &str) {
+        let mut events = self.events.as_ref().clone();
+        for event in events.iter_mut().filter(|e| e.id == id) {
+            event.deleted = true;
+            event.last_modified = now();
+        }
+        self.events = Rc::new(events);
+        self.save();
+        self.push();
+    }
+
+    /// Merge events received from the server, keeping the most recent version of each event
+    pub fn merge(&mut self, remote_events: Vec<PersonalEvent>) {
+        let mut events = self.events.as_ref().clone();
+        for remote_event in remote_events {
+            match events.iter_mut().find(|e| e.id == remote_event.id) {
+                Some(local_event) if local_event.last_modified >= remote_event.last_modified => (),
+                Some(local_event) => *local_event = remote_event,
+                None => events.push(remote_event),
+            }
+        }
+        self.events = Rc::new(events);
+        self.save();
+        self.push();
+    }
+
+    pub fn sync_enabled() -> bool {
+        let local_storage = window().local_storage().unwrap().unwrap();
+        matches!(local_storage.get("personal_events_sync"), Ok(Some(v)) if v == "true")
+    }
+
+    pub fn set_sync_enabled(&self, enabled: bool, app_link: AppLink) {
+        let local_storage = window().local_storage().unwrap().unwrap();
+        local_storage.set("personal_events_sync", &enabled.to_string()).unwrap();
+        if enabled {
+            Self::fetch(app_link);
+        }
+    }
+
+    fn fetch(app_link: AppLink) {
+        spawn_local(async move {
+            match api_get::<Vec<PersonalEvent>>("personal-events").await {
+                Ok(events) => app_link.send_message(AppMsg::PersonalEventsSuccess(events)),
+                Err(e) => app_link.send_message(AppMsg::ApiFailure(e)),
+            }
+        });
+    }
+
+    fn push(&self) {
+        if !Self::sync_enabled() {
+            return;
+        }
+        let events = self.events.as_ref().clone();
+        spawn_local(async move {
+            if let Err(e) = api_post(events, "personal-events").await {
+                e.handle_api_error();
+            }
+        });
+    }
+}
+
+pub enum EditorMsg {
+    Save,
+    Delete,
+    Close,
+}
+
+#[derive(Properties, Clone)]
+pub struct EditorProps {
+    pub event: PersonalEvent,
+    /// Whether the event is already saved, in which case it can be deleted
+    pub existing: bool,
+    pub agenda_link: AgendaLink,
+}
+
+impl PartialEq for EditorProps {
+    fn eq(&self, other: &Self) -> bool {
+        self.event == other.event && self.existing == other.existing
+    }
+}
+
+/// Form to create or edit a personal event
+pub struct PersonalEventEditor;
+
+impl PersonalEventEditor {
+    fn input_value(id: &str) -> String {
+        let el = window().doc().get_element_by_id(id).unwrap();
+        if let Some(input) = el.dyn_ref::<HtmlInputElement>() {
+            return input.value();
+        }
+        if let Some(select) = el.dyn_ref::<web_sys::HtmlSelectElement>() {
+            return select.value();
+        }
+        el.dyn_into::<web_sys::HtmlTextAreaElement>().map(|textarea| textarea.value()).unwrap_or_default()
+    }
+
+    /// Read the form, returning `None` after telling the user what is wrong if it is invalid
+    fn read_event(ctx: &Context<Self>) -> Option<PersonalEvent> {
+        let mut event = ctx.props().event.clone();
+        event.title = Self::input_value("personal-title-input").trim().to_string();
+        if event.title.is_empty() {
+            alert_no_reporting(t("Veuillez donner un titre à l'événement."));
+            return None;
+        }
+
+        let Ok(date) = NaiveDate::parse_from_str(&Self::input_value("personal-date-input"), "%Y-%m-%d") else {
+            alert_no_reporting(t("Veuillez indiquer une date valide."));
+            return None;
+        };
+        let (Ok(start), Ok(end)) = (
+            NaiveTime::parse_from_str(&Self::input_value("personal-start-input"), "%H:%M"),
+            NaiveTime::parse_from_str(&Self::input_value("personal-end-input"), "%H:%M"),
+        ) else {
+            alert_no_reporting(t("Veuillez indiquer une heure de début et une heure de fin valides."));
+            return None;
+        };
+        // Times skipped by a DST change don't exist
+        let (Some(start), Some(end)) = (
+            user_tz().from_local_datetime(&date.and_time(start)).earliest(),
+            user_tz().from_local_datetime(&date.and_time(end)).earliest(),
+        ) else {
+            alert_no_reporting(t("Veuillez indiquer une heure de début et une heure de fin valides."));
+            return None;
+        };
+        if end <= start {
+            alert_no_reporting(t("L'événement doit se terminer après avoir commencé."));
+            return None;
+        }
+        event.start_unixtime = start.timestamp() as u64;
+        event.end_unixtime = end.timestamp() as u64;
+
+        event.color = Self::input_value("personal-color-input");
+        event.recurrence = match Self::input_value("personal-recurrence-input").as_str() {
+            "daily" => Some(Recurrence::Daily),
+            "weekly" => Some(Recurrence::Weekly),
+            "monthly" => Some(Recurrence::Monthly),
+            _ => None,
+        };
+        event.recurrence_end = NaiveDate::parse_from_str(&Self::input_value("personal-recurrence-end-input"), "%Y-%m-%d").ok();
+        event.notes = Self::input_value("personal-notes-input");
+
+        Some(event)
+    }
+}
+
+impl Component for PersonalEventEditor {
+    type Message = EditorMsg;
+    type Properties = EditorProps;
+
+    fn create(_ctx: &Context<Self>) -> Self { Self }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            EditorMsg::Save => {
+                let Some(event) = Self::read_event(ctx) else { return false };
+                ctx.props().agenda_link.send_message(AgendaMsg::AppMsg(AppMsg::SavePersonalEvent(event)));
+                ctx.props().agenda_link.send_message(AgendaMsg::CloseEditor);
+            }
+            EditorMsg::Delete => {
+                ctx.props().agenda_link.send_message(AgendaMsg::AppMsg(AppMsg::DeletePersonalEvent(ctx.props().event.id.clone())));
+                ctx.props().agenda_link.send_message(AgendaMsg::CloseEditor);
+            }
+            EditorMsg::Close => ctx.props().agenda_link.send_message(AgendaMsg::CloseEditor),
+        }
+        false
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let event = &ctx.props().event;
+        let start = user_tz().timestamp_opt(event.start_unixtime as i64, 0).unwrap();
+        let end = user_tz().timestamp_opt(event.end_unixtime as i64, 0).unwrap();
+
+        let title = event.title.clone();
+        let date = start.format("%Y-%m-%d").to_string();
+        let start_time = start.format("%H:%M").to_string();
+        let end_time = end.format("%H:%M").to_string();
+        let color = event.color.clone();
+        let notes = event.notes.clone();
+        let recurrence_end = event.recurrence_end.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default();
+        let no_recurrence = event.recurrence.is_none();
+        let daily = event.recurrence == Some(Recurrence::Daily);
+        let weekly = event.recurrence == Some(Recurrence::Weekly);
+        let monthly = event.recurrence == Some(Recurrence::Monthly);
+        let existing = ctx.props().existing;
+
+        template_html!(
+            "src/personal/editor.html",
+            onclick_save = {ctx.link().callback(|_| EditorMsg::Save)},
+            onclick_delete = {ctx.link().callback(|_| EditorMsg::Delete)},
+            onclick_close = {ctx.link().callback(|_| EditorMsg::Close)},
+            ...
+        )
+    }
+}
@@ -0,0 +1,357 @@
+//! Parser for the gettext message catalogs of the `locales` directory.
+//!
+//! This module only depends on `std` because it is also included by `build.rs`, which checks that catalogs are complete.
+//!
+//! Besides the standard `Plural-Forms` header, catalogs can have an `X-Ordinal-Forms` header with the same syntax.
+//! It selects the form of the messages with the `ordinal` context.
+
+use std::collections::HashMap;
+
+/// Context of the messages whose forms are selected by the ordinal rule
+pub const ORDINAL_CONTEXT: &str = "ordinal";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op { Or, And, Eq, Ne, Lt, Le, Gt, Ge, Add, Sub, Mul, Div, Rem }
+
+/// Expression of a `plural=` rule, in the C syntax used by gettext
+#[derive(Debug, Clone)]
+pub enum PluralRule {
+    N,
+    Number(u64),
+    Not(Box<PluralRule>),
+    Binary(Op, Box<PluralRule>, Box<PluralRule>),
+    Ternary(Box<PluralRule>, Box<PluralRule>, Box<PluralRule>),
+}
+
+impl PluralRule {
+    pub fn eval(&self, n: u64) -> u64 {
+        match self {
+            PluralRule::N => n,
+            PluralRule::Number(value) => *value,
+            PluralRule::Not(e) => (e.eval(n) == 0) as u64,
+            PluralRule::Ternary(condition, a, b) => if condition.eval(n) != 0 { a.eval(n) } else { b.eval(n) },
+            PluralRule::Binary(op, a, b) => {
+                let (a, b) = (a.eval(n), b.eval(n));
+                match op {
+                    Op::Or => (a != 0 || b != 0) as u64,
+                    Op::And => (a != 0 && b != 0) as u64,
+                    Op::Eq => (a == b) as u64,
+                    Op::Ne => (a != b) as u64,
+                    Op::Lt => (a < b) as u64,
+                    Op::Le => (a <= b) as u64,
+                    Op::Gt => (a > b) as u64,
+                    Op::Ge => (a >= b) as u64,
+                    Op::Add => a.wrapping_add(b),
+                    Op::Sub => a.wrapping_sub(b),
+                    Op::Mul => a.wrapping_mul(b),
+                    Op::Div => a.checked_div(b).unwrap_or(0),
+                    Op::Rem => a.checked_rem(b).unwrap_or(0),
+                }
+            }
+        }
+    }
+
+    /// Parse the value of a `Plural-Forms` header, such as `nplurals=2; plural=(n > 1);`
+    pub fn parse_header(header: &str) -> Result<PluralRule, String> {
+        let expression = header.split(';')
+            .find_map(|part| part.trim().strip_prefix("plural="))
+            .ok_or_else(|| format!("Missing plural expression in {header:?}"))?;
+        let tokens = tokenize(expression)?;
+        let mut parser = RuleParser { tokens: &tokens, position: 0 };
+        let rule = parser.ternary()?;
+        match parser.position == tokens.len() {
+            true => Ok(rule),
+            false => Err(format!("Unexpected tokens in {expression:?}")),
+        }
+    }
+}
+
+fn tokenize(expression: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let chars = expression.chars().collect::<Vec<_>>();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            tokens.push(chars[start..i].iter().collect());
+        } else if i + 1 < chars.len() && ["==", "!=", "<=", ">=", "&&", "||"].contains(&format!("{c}{}", chars[i + 1]).as_str()) {
+            tokens.push(format!("{c}{}", chars[i + 1]));
+            i += 2;
+        } else if "n!<>+-*/%?:()".contains(c) {
+            tokens.push(c.to_string());
+            i += 1;
+        } else {
+            return Err(format!("Unexpected character {c:?} in {expression:?}"));
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent parser following the precedence of C operators
+struct RuleParser<'a> {
+    tokens: &'a [String],
+    position: usize,
+}
+
+impl<'a> RuleParser<'a> {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.position).map(|t| t.as_str())
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), String> {
+        match self.peek() == Some(token) {
+            true => { self.position += 1; Ok(()) },
+            false => Err(format!("Expected {token:?} in plural rule")),
+        }
+    }
+
+    fn ternary(&mut self) -> Result<PluralRule, String> {
+        let condition = self.binary(0)?;
+        if self.peek() != Some("?") {
+            return Ok(condition);
+        }
+        self.position += 1;
+        let a = self.ternary()?;
+        self.expect(":")?;
+        let b = self.ternary()?;
+        Ok(PluralRule::Ternary(Box::new(condition), Box::new(a), Box::new(b)))
+    }
+
+    fn binary(&mut self, level: usize) -> Result<PluralRule, String> {
+        const LEVELS: &[&[(&str, Op)]] = &[
+            &[("||", Op::Or)],
+            &[("&&", Op::And)],
+            &[("==", Op::Eq), ("!=", Op::Ne)],
+            &[("<", Op::Lt), ("<=", Op::Le), (">", Op::Gt), (">=", Op::Ge)],
+            &[("+", Op::Add), ("-", Op::Sub)],
+            &[("*", Op::Mul), ("/", Op::Div), ("%", Op::Rem)],
+        ];
+        let Some(operators) = LEVELS.get(level) else { return self.unary() };
+        let mut left = self.binary(level + 1)?;
+        while let Some((_, op)) = operators.iter().find(|(token, _)| self.peek() == Some(*token)) {
+            self.position += 1;
+            let right = self.binary(level + 1)?;
+            left = PluralRule::Binary(*op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<PluralRule, String> {
+        let token = self.peek().ok_or("Unexpected end of plural rule")?.to_string();
+        self.position += 1;
+        match token.as_str() {
+            "n" => Ok(PluralRule::N),
+            "!" => Ok(PluralRule::Not(Box::new(self.unary()?))),
+            "(" => {
+                let rule = self.ternary()?;
+                self.expect(")")?;
+                Ok(rule)
+            }
+            number => number.parse().map(PluralRule::Number).map_err(|_| format!("Unexpected {number:?} in plural rule")),
+        }
+    }
+}
+
+/// A message catalog of one language
+#[derive(Debug, Default)]
+pub struct Catalog {
+    /// Fields of the header, such as `X-Language-Name`, the name of the language in itself
+    pub headers: HashMap<String, String>,
+    /// Translations by context and message id. Messages with a plural have one translation per form.
+    pub messages: HashMap<(Option<String>, String), Vec<String>>,
+    pub plural_rule: Option<PluralRule>,
+    pub ordinal_rule: Option<PluralRule>,
+}
+
+/// Decode a quoted string of a catalog
+fn unquote(value: &str, line_number: usize) -> Result<String, String> {
+    let inner = value.trim().strip_prefix('"').and_then(|v| v.strip_suffix('"'))
+        .ok_or_else(|| format!("Line {line_number}: expected a quoted string"))?;
+    let mut unquoted = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') => unquoted.push('\n'),
+                Some('t') => unquoted.push('\t'),
+                Some(c @ ('"' | '\\')) => unquoted.push(c),
+                _ => return Err(format!("Line {line_number}: invalid escape sequence")),
+            },
+            c => unquoted.push(c),
+        }
+    }
+    Ok(unquoted)
+}
+
+#[derive(Default)]
+struct Entry {
+    context: Option<String>,
+    id: Option<String>,
+    translations: Vec<String>,
+}
+
+impl Catalog {
+    pub fn parse(text: &str) -> Result<Catalog, String> {
+        let mut catalog = Catalog::default();
+        let mut entry = Entry::default();
+        // Field the next continuation line is appended to
+        let mut last_field: Option<(&str, usize)> = None;
+
+        for (i, line) in text.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('"') {
+                let value = unquote(line, line_number)?;
+                match last_field {
+                    Some(("msgctxt", _)) => entry.context.get_or_insert_with(String::new).push_str(&value),
+                    Some(("msgid", _)) => entry.id.get_or_insert_with(String::new).push_str(&value),
+                    Some(("msgstr", index)) => entry.translations[index].push_str(&value),
+                    _ => (),
+                }
+                continue;
+            }
+
+            let (keyword, value) = line.split_once(char::is_whitespace).ok_or_else(|| format!("Line {line_number}: expected a keyword and a value"))?;
+            let value = unquote(value, line_number)?;
+            let keyword = match keyword {
+                "msgctxt" | "msgid" if !entry.translations.is_empty() => {
+                    catalog.insert(std::mem::take(&mut entry))?;
+                    keyword
+                }
+                keyword => keyword,
+            };
+            match keyword {
+                "msgctxt" => {
+                    entry.context = Some(value);
+                    last_field = Some(("msgctxt", 0));
+                }
+                "msgid" => {
+                    entry.id = Some(value);
+                    last_field = Some(("msgid", 0));
+                }
+                // The plural of the message id is only used when there is no translation
+                "msgid_plural" => last_field = None,
+                "msgstr" => {
+                    entry.translations.push(value);
+                    last_field = Some(("msgstr", entry.translations.len() - 1));
+                }
+                keyword => {
+                    let index = keyword.strip_prefix("msgstr[").and_then(|k| k.strip_suffix(']')).and_then(|k| k.parse::<usize>().ok())
+                        .ok_or_else(|| format!("Line {line_number}: unknown keyword {keyword}"))?;
+                    if index != entry.translations.len() {
+                        return Err(format!("Line {line_number}: plural forms must be in order"));
+                    }
+                    entry.translations.push(value);
+                    last_field = Some(("msgstr", index));
+                }
+            }
+        }
+        if entry.id.is_some() {
+            catalog.insert(entry)?;
+        }
+        Ok(catalog)
+    }
+
+    fn insert(&mut self, entry: Entry) -> Result<(), String> {
+        let id = entry.id.ok_or("Translation without message id")?;
+        if id.is_empty() {
+            // The header is the translation of the empty message
+            for line in entry.translations.first().map(|h| h.lines()).into_iter().flatten() {
+                let Some((name, value)) = line.split_once(':') else { continue };
+                match name {
+                    "Plural-Forms" => self.plural_rule = Some(PluralRule::parse_header(value)?),
+                    "X-Ordinal-Forms" => self.ordinal_rule = Some(PluralRule::parse_header(value)?),
+                    _ => (),
+                }
+                self.headers.insert(name.to_string(), value.trim().to_string());
+            }
+            return Ok(());
+        }
+        // Untranslated messages are left out, so that they fall back to the message id
+        if entry.translations.iter().any(|t| !t.is_empty()) {
+            self.messages.insert((entry.context, id), entry.translations);
+        }
+        Ok(())
+    }
+
+    /// Index of the plural form to use for `n`
+    pub fn plural_form(&self, n: u64, ordinal: bool) -> usize {
+        let rule = if ordinal { &self.ordinal_rule } else { &self.plural_rule };
+        match rule {
+            Some(rule) => rule.eval(n) as usize,
+            None => (n != 1) as usize,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn forms(header: &str, numbers: impl IntoIterator<Item = u64>) -> Vec<u64> {
+        let rule = PluralRule::parse_header(header).unwrap();
+        numbers.into_iter().map(|n| rule.eval(n)).collect()
+    }
+
+    #[test]
+    fn simple_rules() {
+        assert_eq!(forms("nplurals=2; plural=(n > 1);", [0, 1, 2, 10]), vec![0, 0, 1, 1]);
+        assert_eq!(forms("nplurals=2; plural=(n != 1);", [0, 1, 2]), vec![1, 0, 1]);
+        assert_eq!(forms("nplurals=1; plural=0;", [0, 1, 2]), vec![0, 0, 0]);
+    }
+
+    #[test]
+    fn english_ordinals() {
+        let header = "nplurals=4; plural=(n % 10 == 1 && n % 100 != 11 ? 0 : n % 10 == 2 && n % 100 != 12 ? 1 : n % 10 == 3 && n % 100 != 13 ? 2 : 3);";
+        assert_eq!(forms(header, [1, 2, 3, 4, 11, 12, 13, 21, 22, 23, 101, 111]), vec![0, 1, 2, 3, 3, 3, 3, 0, 1, 2, 0, 3]);
+    }
+
+    #[test]
+    fn precedence() {
+        // Polish, where comparisons bind tighter than `&&`, itself tighter than `||`
+        let header = "nplurals=3; plural=(n==1 ? 0 : n%10>=2 && n%10<=4 && (n%100<10 || n%100>=20) ? 1 : 2);";
+        assert_eq!(forms(header, [1, 2, 4, 5, 12, 22, 25]), vec![0, 1, 1, 2, 2, 1, 2]);
+        assert_eq!(forms("nplurals=2; plural=1 + 2 * 3 - !n;", [0, 1]), vec![6, 7]);
+    }
+
+    #[test]
+    fn invalid_rules() {
+        assert!(PluralRule::parse_header("nplurals=2;").is_err());
+        assert!(PluralRule::parse_header("nplurals=2; plural=(n > 1;").is_err());
+        assert!(PluralRule::parse_header("nplurals=2; plural=n > 1 1;").is_err());
+        assert!(PluralRule::parse_header("nplurals=2; plural=m;").is_err());
+    }
+
+    #[test]
+    fn catalog() {
+        let catalog = Catalog::parse(r#"
+msgid ""
+msgstr ""
+"Plural-Forms: nplurals=2; plural=(n != 1);\n"
+
+msgid "Bonjour"
+msgstr "Hello"
+
+msgid "{n} jour"
+msgid_plural "{n} jours"
+msgstr[0] "{n} day"
+msgstr[1] "{n} days"
+
+msgid "Non traduit"
+msgstr ""
+"#).unwrap();
+        assert_eq!(catalog.messages[&(None, String::from("Bonjour"))], vec!["Hello"]);
+        assert_eq!(catalog.messages[&(None, String::from("{n} jour"))], vec!["{n} day", "{n} days"]);
+        assert!(!catalog.messages.contains_key(&(None, String::from("Non traduit"))));
+        assert_eq!(catalog.plural_form(2, false), 1);
+    }
+}
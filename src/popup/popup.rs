@@ -11,6 +11,7 @@ pub enum PopupMsg {
     ReloadComments,
     Comment,
     CommentsLoaded(Vec<Comment>),
+    Export,
 }
 
 #[derive(Properties, Clone)]
@@ -61,6 +62,11 @@ impl Component for Popup {
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
             PopupMsg::CommentsLoaded(mut new_comments) => {
+                // What is displayed here doesn't need to be notified anymore
+                if let Some(uid) = ctx.props().user_info.as_ref().as_ref().map(|u| u.uid) {
+                    let event = &ctx.props().event;
+                    CommentWatch::load().update(&event.eid, &event.format_name(), &new_comments, uid);
+                }
                 new_comments.sort_by_key(|c| c.downvotes as isize - c.upvotes as isize);
                 self.comments = Some(new_comments);
                 true
@@ -102,6 +108,15 @@ impl Component for Popup {
                 });
                 true
             }
+            PopupMsg::Export => {
+                let ics = events_to_ics(std::iter::once(&ctx.props().event));
+                let name = match safe_filename(&ctx.props().event.summary) {
+                    name if name.is_empty() => String::from("insagenda"),
+                    name => name,
+                };
+                download_ics(&format!("{name}.ics"), &ics);
+                false
+            }
             PopupMsg::TriggerFriendCounter => {
                 self.friend_counter_folded = !self.friend_counter_folded;
                 true
@@ -124,11 +139,11 @@ impl Component for Popup {
         let friend_count = friends.len();
         let friend_counter_folded = friend_count != 0 && self.friend_counter_folded;
         let friend_counter_unfolded = friend_count != 0 && !self.friend_counter_folded;
-        let only_one_friend = friend_count == 1;
+        let friend_count_label = tn("Un ami suit ce cours", "{n} amis suivent ce cours", friend_count as u64, &[]);
         let z_index_iter = 1..friend_count+1;
 
         let event_color = COLORS.get(&ctx.props().event.summary);
-        let summary = &ctx.props().event.summary;
+        let color_help = tf("Cliquez sur la prévisualisation ci-dessous pour changer la couleur de tous les cours de {summary}.", &[("summary", &ctx.props().event.summary)]);
         let name = ctx.props().event.format_name();
         let opt_location = ctx.props().event.format_location();
 
@@ -149,6 +164,7 @@ impl Component for Popup {
         let user_avatar = format!("https://api.dicebear.com/5.x/identicon/svg?seed={}", ctx.props().user_info.as_ref().as_ref().map(|u| u.uid).unwrap_or(0));
         let user_name = ctx.props().user_info.as_ref().as_ref().map(|u| u.email.0.split('@').next().unwrap().to_string()).unwrap_or(String::from("inconnu"));
         let onclick_comment = ctx.link().callback(|_| PopupMsg::Comment);
+        let onclick_export = ctx.link().callback(|_| PopupMsg::Export);
 
         template_html!(
             "src/popup/popup.html",
@@ -160,6 +176,8 @@ impl Component for Popup {
             onclick_fold = {ctx.link().callback(|_| PopupMsg::TriggerFriendCounter)},
             opt_location = {&opt_location},
             event_color = {event_color.clone()},
+            event_fill = {event_background(&event_color)},
+            event_text_color = {event_text_color(&event_color)},
             alt_iter = { names.iter().map(|name| format!("Avatar of {}", name)) },
             picture_iter = { friends.iter().map(|friend| friend.profile_url()) },
             ...
@@ -1,7 +1,7 @@
 pub use crate::{
-    agenda::*, alert::*, api::*, calendar::*, checkbox::*, colors::*, comment::*,
-    event::*, friends::*, glider_selector::*, log, notifications::*,
-    popup::Popup, popup::*, settings::*, sortable::*, survey::*, tabbar::*, translation::*,
+    accessibility::*, account::*, agenda::*, alert::*, api::*, archive::*, badge::*, calendar::*, checkbox::*, colors::*, comment::*, datetime::*,
+    event::*, free_time::*, friends::*, friends_now::*, glider_selector::*, groups::*, ics::*, imported::*, log, notifications::*, personal::*,
+    popup::Popup, popup::*, push::*, reminders::*, rich_text::*, settings::*, sortable::*, survey::*, tabbar::*, translation::*,
     util::*, App, Msg as AppMsg, Page,
 };
 pub use chrono::{
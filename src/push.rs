@@ -0,0 +1,211 @@
+//! Web Push subscription, so that announcements and friend requests are received while the app is closed.
+//!
+//! The service worker shows pushed notifications and forwards them to open tabs with a `push` message,
+//! which is turned into [AppMsg::PushReceived]. Since that message only reaches tabs that are open, the data of
+//! the enabled categories is also refreshed whenever the app becomes visible again.
+
+use web_sys::{MessageEvent, ServiceWorkerRegistration};
+use crate::prelude::*;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PushCategory {
+    Announcement,
+    FriendRequest,
+}
+
+#[derive(Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct PushSettings {
+    pub announcements: bool,
+    pub friend_requests: bool,
+}
+
+impl PushSettings {
+    pub fn load() -> Self {
+        let local_storage = window().local_storage().unwrap().unwrap();
+        let Ok(Some(settings)) = local_storage.get("push_settings") else { return Self::default() };
+        serde_json::from_str(&settings).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        let local_storage = window().local_storage().unwrap().unwrap();
+        local_storage.set("push_settings", &serde_json::to_string(self).unwrap()).unwrap();
+    }
+
+    pub fn categories(&self) -> Vec<PushCategory> {
+        let mut categories = Vec::new();
+        if self.announcements {
+            categories.push(PushCategory::Announcement);
+        }
+        if self.friend_requests {
+            categories.push(PushCategory::FriendRequest);
+        }
+        categories
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct PushSubscriptionKeys {
+    p256dh: String,
+    auth: String,
+}
+
+/// A push subscription, as returned by `PushSubscription.toJSON()`
+#[derive(Serialize, Deserialize)]
+struct PushSubscriptionJson {
+    endpoint: String,
+    keys: PushSubscriptionKeys,
+}
+
+#[derive(Serialize)]
+struct PushSubscriptionRequest {
+    subscription: PushSubscriptionJson,
+    categories: Vec<PushCategory>,
+}
+
+/// Message forwarded by the service worker when a push is received
+#[derive(Deserialize)]
+struct PushMessage {
+    #[serde(rename = "type")]
+    kind: String,
+    category: PushCategory,
+}
+
+async fn get_push_key() -> Result<String, ApiError> {
+    api_get("push/key").await
+}
+
+/// Call a method of the `PushManager` of the registration, which returns a promise
+async fn call_push_manager(registration: &ServiceWorkerRegistration, method: &str, args: &Array) -> Result<JsValue, JsValue> {
+    let push_manager = Reflect::get(registration, &"pushManager".into())?;
+    let method: Function = Reflect::get(&push_manager, &method.into())?.dyn_into()?;
+    let promise: js_sys::Promise = method.apply(&push_manager, args)?.dyn_into()?;
+    JsFuture::from(promise).await
+}
+
+/// Decode an URL-safe base64 string, as used for application server keys
+fn decode_base64_url(input: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in input.trim_end_matches('=').chars() {
+        let value = match c {
+            'A'..='Z' => c as u32 - 'A' as u32,
+            'a'..='z' => c as u32 - 'a' as u32 + 26,
+            '0'..='9' => c as u32 - '0' as u32 + 52,
+            '-' | '+' => 62,
+            '_' | '/' => 63,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | value;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+async fn subscribe(registration: &ServiceWorkerRegistration, categories: Vec<PushCategory>) -> Result<(), ApiError> {
+    let mut subscription = call_push_manager(registration, "getSubscription", &Array::new()).await?;
+    if subscription.is_null() {
+        let key = get_push_key().await?;
+        let Some(key) = decode_base64_url(&key) else { return Err(ApiError::Unknown(JsValue::from_str("Invalid push key"))) };
+        let options = js_sys::Object::new();
+        Reflect::set(&options, &"userVisibleOnly".into(), &true.into())?;
+        Reflect::set(&options, &"applicationServerKey".into(), &js_sys::Uint8Array::from(key.as_slice()))?;
+        subscription = call_push_manager(registration, "subscribe", &Array::of1(&options)).await?;
+    }
+
+    let to_json: Function = Reflect::get(&subscription, &"toJSON".into())?.dyn_into()?;
+    let subscription = to_json.call0(&subscription)?;
+    let subscription: PushSubscriptionJson = serde_wasm_bindgen::from_value(subscription).map_err(|e| ApiError::Unknown(JsValue::from_str(&e.to_string())))?;
+    api_post(PushSubscriptionRequest { subscription, categories }, "push/subscribe").await
+}
+
+/// Remove the subscription of the browser, returning its endpoint if there was one
+async fn remove_browser_subscription(registration: &ServiceWorkerRegistration) -> Result<Option<String>, JsValue> {
+    let subscription = call_push_manager(registration, "getSubscription", &Array::new()).await?;
+    if subscription.is_null() {
+        return Ok(None);
+    }
+
+    let endpoint = Reflect::get(&subscription, &"endpoint".into())?.as_string().unwrap_or_default();
+    let unsubscribe: Function = Reflect::get(&subscription, &"unsubscribe".into())?.dyn_into()?;
+    JsFuture::from(unsubscribe.call0(&subscription)?.dyn_into::<js_sys::Promise>()?).await?;
+    Ok(Some(endpoint))
+}
+
+async fn unsubscribe(registration: &ServiceWorkerRegistration) -> Result<(), ApiError> {
+    match remove_browser_subscription(registration).await? {
+        Some(endpoint) => api_post(endpoint, "push/unsubscribe").await,
+        None => Ok(()),
+    }
+}
+
+/// Remove the subscription of the browser without telling the server, which already forgot it when the account was deleted
+pub(crate) async fn forget_push_subscription() -> Result<(), JsValue> {
+    let Some(registration) = service_worker_registration().await else { return Ok(()) };
+    remove_browser_subscription(&registration).await.map(|_| ())
+}
+
+/// Subscribe to the enabled categories, or remove the subscription if there is none.
+/// Returns whether the subscription matches the settings, after telling the user what went wrong otherwise.
+pub async fn update_push_subscription(settings: &PushSettings) -> bool {
+    let categories = settings.categories();
+    if !categories.is_empty() && !request_notification_permission().await {
+        alert_no_reporting(t("Les notifications ont été refusées par le navigateur."));
+        return false;
+    }
+    let Some(registration) = service_worker_registration().await else {
+        alert_no_reporting(t("Impossible de modifier l'abonnement aux notifications push."));
+        return false;
+    };
+    let result = match categories.is_empty() {
+        true => unsubscribe(&registration).await,
+        false => subscribe(&registration, categories).await,
+    };
+    match result {
+        Ok(()) => true,
+        Err(e) => {
+            if let ApiError::Unknown(_) = e {
+                alert_no_reporting(t("Impossible de modifier l'abonnement aux notifications push."));
+            }
+            e.handle_api_error();
+            false
+        }
+    }
+}
+
+/// Forward the pushes relayed by the service worker to the app, and catch up on the ones received while it was hidden or closed
+pub fn listen_to_pushes(app_link: AppLink) {
+    let link = app_link.clone();
+    let on_visibility_change = Closure::wrap(Box::new(move || {
+        if window().doc().hidden() {
+            return;
+        }
+        for category in PushSettings::load().categories() {
+            match category {
+                PushCategory::Announcement => <Vec<AnnouncementDesc> as CachedData>::refresh(link.clone()),
+                PushCategory::FriendRequest => <FriendLists as CachedData>::refresh(link.clone()),
+            }
+        }
+    }) as Box<dyn FnMut()>);
+    if let Err(e) = window().doc().add_event_listener_with_callback("visibilitychange", on_visibility_change.as_ref().unchecked_ref()) {
+        sentry_report(e);
+    }
+    on_visibility_change.forget();
+
+    let on_message = Closure::wrap(Box::new(move |event: MessageEvent| {
+        let Ok(message) = serde_wasm_bindgen::from_value::<PushMessage>(event.data()) else { return };
+        if message.kind == "push" {
+            app_link.send_message(AppMsg::PushReceived(message.category));
+        }
+    }) as Box<dyn FnMut(_)>);
+    let container = window().navigator().service_worker();
+    if let Err(e) = container.add_event_listener_with_callback("message", on_message.as_ref().unchecked_ref()) {
+        sentry_report(e);
+    }
+    on_message.forget();
+}
@@ -0,0 +1,258 @@
+//! Reminders shown a few minutes before classes.
+//!
+//! Reminders are handed to the service worker (`/sw.js`) in a `reminders` message, so that it can show them while the app is closed.
+//! Browsers supporting notification triggers also get them scheduled directly as notifications of the registration.
+
+use web_sys::{Notification, NotificationPermission, ServiceWorkerRegistration};
+use crate::prelude::*;
+
+/// Reminders are only scheduled for classes starting within this number of seconds
+const REMINDER_HORIZON: u64 = 7 * 86400;
+
+/// Tag prefix of reminder notifications, used to replace them when the schedule changes
+const REMINDER_TAG_PREFIX: &str = "reminder-";
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ReminderMode {
+    AllClasses,
+    FirstOfDay,
+    /// Only the courses that were explicitly selected
+    SelectedCourses,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReminderSettings {
+    pub enabled: bool,
+    pub minutes_before: u32,
+    pub mode: ReminderMode,
+    /// Courses (by summary) for which reminders are disabled, in the other modes
+    pub excluded: Vec<String>,
+    /// Courses (by summary) for which reminders are enabled, in [ReminderMode::SelectedCourses]
+    pub selected: Vec<String>,
+}
+
+impl Default for ReminderSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            minutes_before: 10,
+            mode: ReminderMode::AllClasses,
+            excluded: Vec::new(),
+            selected: Vec::new(),
+        }
+    }
+}
+
+impl ReminderSettings {
+    pub fn load() -> Self {
+        let local_storage = window().local_storage().unwrap().unwrap();
+        let Ok(Some(settings)) = local_storage.get("reminder_settings") else { return Self::default() };
+        serde_json::from_str(&settings).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        self.save_local();
+        SETTINGS_SYNC.record("reminders");
+    }
+
+    /// Save without syncing the change with the server
+    pub fn save_local(&self) {
+        let local_storage = window().local_storage().unwrap().unwrap();
+        local_storage.set("reminder_settings", &serde_json::to_string(self).unwrap()).unwrap();
+    }
+
+    /// Whether reminders are wanted for this course
+    pub fn applies_to(&self, summary: &str) -> bool {
+        match self.mode {
+            ReminderMode::SelectedCourses => self.selected.iter().any(|s| s == summary),
+            _ => !self.excluded.iter().any(|s| s == summary),
+        }
+    }
+
+    pub fn set_applies_to(&mut self, summary: String, applies: bool) {
+        let list = match self.mode {
+            ReminderMode::SelectedCourses => &mut self.selected,
+            _ => &mut self.excluded,
+        };
+        list.retain(|s| *s != summary);
+        if applies == (self.mode == ReminderMode::SelectedCourses) {
+            list.push(summary);
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Reminder {
+    pub tag: String,
+    pub title: String,
+    pub body: String,
+    /// When to show the reminder, in milliseconds, as expected by JavaScript
+    pub timestamp: f64,
+}
+
+/// Message sent to the service worker
+#[derive(Serialize)]
+struct RemindersMessage<'a> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    reminders: &'a [Reminder],
+}
+
+/// Classes that get a reminder, among classes sorted by start time that include the ones of the day that already started.
+/// The first class of each day is found before leaving out the classes starting too soon for a reminder.
+fn reminded_classes<E>(classes: &[E], start: impl Fn(&E) -> u64, mode: ReminderMode, delay: u64, now: u64, tz: Tz) -> Vec<&E> {
+    let mut last_day = None;
+    classes.iter().filter(|class| {
+        let day = tz.timestamp_opt(start(class) as i64, 0).unwrap().date_naive();
+        let first_of_day = last_day != Some(day);
+        last_day = Some(day);
+        (mode != ReminderMode::FirstOfDay || first_of_day) && start(class) >= now + delay
+    }).collect()
+}
+
+/// List the reminders to show for the upcoming classes
+pub fn compute_reminders(events: &[RawEvent], settings: &ReminderSettings, now: u64) -> Vec<Reminder> {
+    if !settings.enabled {
+        return Vec::new();
+    }
+
+    let delay = settings.minutes_before as u64 * 60;
+    // Classes of the last 24 hours are kept, so that the first class of today is known even once it started
+    let first = events.partition_point(|e| e.start_unixtime + 86400 < now);
+    // Excluded courses are left out first, so that the first class of the day is the first one with a reminder
+    let classes = events[first..].iter()
+        .take_while(|e| e.start_unixtime < now + REMINDER_HORIZON)
+        .filter(|e| settings.applies_to(&e.summary))
+        .collect::<Vec<_>>();
+
+    let tz = user_tz();
+    let mut reminders = Vec::new();
+    for event in reminded_classes(&classes, |e| e.start_unixtime, settings.mode, delay, now, tz) {
+        let start = tz.timestamp_opt(event.start_unixtime as i64, 0).unwrap();
+        let mut body = format!("{} {}", t("À"), format_time(start));
+        if let Some(location) = event.format_location() {
+            body.push_str(&format!(" · {location}"));
+        }
+        reminders.push(Reminder {
+            tag: format!("{REMINDER_TAG_PREFIX}{}", event.eid),
+            title: event.format_name(),
+            body,
+            timestamp: ((event.start_unixtime - delay) * 1000) as f64,
+        });
+    }
+    reminders
+}
+
+/// Whether the browser can show notifications from a service worker
+pub fn reminders_supported() -> bool {
+    Reflect::has(&window(), &"Notification".into()).unwrap_or(false)
+        && Reflect::has(&window().navigator(), &"serviceWorker".into()).unwrap_or(false)
+}
+
+/// Ask the browser for the permission to show notifications
+pub async fn request_notification_permission() -> bool {
+    if Notification::permission() == NotificationPermission::Granted {
+        return true;
+    }
+    let Ok(promise) = Notification::request_permission() else { return false };
+    match JsFuture::from(promise).await {
+        Ok(permission) => permission.as_string().as_deref() == Some("granted"),
+        Err(_) => false,
+    }
+}
+
+pub(crate) async fn service_worker_registration() -> Option<ServiceWorkerRegistration> {
+    let container = window().navigator().service_worker();
+    let ready = container.ready().ok()?;
+    JsFuture::from(ready).await.ok()?.dyn_into().ok()
+}
+
+/// Remove the reminders scheduled with notification triggers
+async fn clear_triggered_reminders(registration: &ServiceWorkerRegistration) -> Result<(), JsValue> {
+    let filter = js_sys::Object::new();
+    Reflect::set(&filter, &"includeTriggered".into(), &true.into())?;
+    let get_notifications: Function = Reflect::get(registration, &"getNotifications".into())?.dyn_into()?;
+    let notifications = JsFuture::from(get_notifications.call1(registration, &filter)?.dyn_into::<js_sys::Promise>()?).await?;
+    for notification in Array::from(&notifications).iter() {
+        let notification: Notification = notification.dyn_into()?;
+        if notification.tag().starts_with(REMINDER_TAG_PREFIX) {
+            notification.close();
+        }
+    }
+    Ok(())
+}
+
+/// Schedule a reminder with a notification trigger, on browsers that support them
+fn trigger_reminder(registration: &ServiceWorkerRegistration, reminder: &Reminder) -> Result<(), JsValue> {
+    let trigger_class: Function = Reflect::get(&window(), &"TimestampTrigger".into())?.dyn_into()?;
+    let trigger = Reflect::construct(&trigger_class, &Array::of1(&reminder.timestamp.into()))?;
+    let options = js_sys::Object::new();
+    Reflect::set(&options, &"tag".into(), &reminder.tag.as_str().into())?;
+    Reflect::set(&options, &"body".into(), &reminder.body.as_str().into())?;
+    Reflect::set(&options, &"icon".into(), &"/assets/logo/logo.svg".into())?;
+    Reflect::set(&options, &"showTrigger".into(), &trigger)?;
+    registration.show_notification_with_options(&reminder.title, options.unchecked_ref())?;
+    Ok(())
+}
+
+/// Replace the scheduled reminders by the ones matching the current schedule and settings
+pub fn schedule_reminders(events: Rc<Vec<RawEvent>>) {
+    spawn_local(update_reminders(events));
+}
+
+/// Replace the scheduled reminders, and wait until it is done
+pub async fn update_reminders(events: Rc<Vec<RawEvent>>) {
+    let settings = ReminderSettings::load();
+    if !reminders_supported() || (settings.enabled && Notification::permission() != NotificationPermission::Granted) {
+        return;
+    }
+    let Some(registration) = service_worker_registration().await else { return };
+    let reminders = compute_reminders(&events, &settings, now() as u64);
+
+    // Let the service worker know about the reminders
+    if let Some(worker) = registration.active() {
+        let message = RemindersMessage { kind: "reminders", reminders: &reminders };
+        match serde_wasm_bindgen::to_value(&message) {
+            Ok(message) => if let Err(e) = worker.post_message(&message) {
+                sentry_report(e);
+            },
+            Err(e) => log!("Failed to serialize reminders: {e}"),
+        }
+    }
+
+    // Schedule them directly where notification triggers are supported
+    if Reflect::has(&window(), &"TimestampTrigger".into()).unwrap_or(false) {
+        if let Err(e) = clear_triggered_reminders(&registration).await {
+            sentry_report(e);
+        }
+        for reminder in &reminders {
+            if let Err(e) = trigger_reminder(&registration, reminder) {
+                sentry_report(e);
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paris(d: u32, h: u32, min: u32) -> u64 {
+        Paris.with_ymd_and_hms(2024, 3, d, h, min, 0).unwrap().timestamp() as u64
+    }
+
+    #[test]
+    fn first_of_day_includes_started_classes() {
+        let classes = [paris(4, 8, 0), paris(4, 10, 30), paris(5, 9, 0), paris(5, 11, 0)];
+        let reminded = reminded_classes(&classes, |start| *start, ReminderMode::FirstOfDay, 600, paris(4, 10, 0), Paris);
+        assert_eq!(reminded, vec![&paris(5, 9, 0)]);
+    }
+
+    #[test]
+    fn all_classes_after_delay() {
+        let classes = [paris(4, 8, 0), paris(4, 10, 5), paris(4, 10, 30), paris(5, 9, 0)];
+        let reminded = reminded_classes(&classes, |start| *start, ReminderMode::AllClasses, 600, paris(4, 10, 0), Paris);
+        assert_eq!(reminded, vec![&paris(4, 10, 30), &paris(5, 9, 0)]);
+    }
+}
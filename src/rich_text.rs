@@ -0,0 +1,386 @@
+//! Rendering of formatted announcements to HTML that is safe to insert in the page.
+//! Everything goes through [`sanitize_html`], which only keeps a small set of formatting tags and checks every URL, so that announcements can't run scripts.
+
+/// Tags that are kept, with the attributes they may have
+const ALLOWED_TAGS: &[(&str, &[&str])] = &[
+    ("a", &["href", "title"]),
+    ("b", &[]),
+    ("blockquote", &[]),
+    ("br", &[]),
+    ("code", &[]),
+    ("em", &[]),
+    ("h3", &[]),
+    ("h4", &[]),
+    ("i", &[]),
+    ("img", &["src", "alt", "title"]),
+    ("li", &[]),
+    ("ol", &[]),
+    ("p", &[]),
+    ("pre", &[]),
+    ("s", &[]),
+    ("strong", &[]),
+    ("u", &[]),
+    ("ul", &[]),
+];
+
+/// Tags that can't be closed
+const VOID_TAGS: &[&str] = &["br", "img"];
+
+/// Tags that are removed along with everything they contain
+const DROPPED_TAGS: &[&str] = &["script", "style", "iframe", "object", "embed", "template", "noscript", "textarea", "title", "svg", "math"];
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Decode the character references of an attribute value, as the browser would before interpreting it
+fn decode_entities(value: &str) -> Option<String> {
+    let mut decoded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(i) = rest.find('&') {
+        decoded.push_str(&rest[..i]);
+        rest = &rest[i + 1..];
+        let end = rest.find(|c: char| !c.is_ascii_alphanumeric() && c != '#').unwrap_or(rest.len());
+        let (name, after) = rest.split_at(end);
+        let c = match name {
+            "amp" => '&',
+            "lt" => '<',
+            "gt" => '>',
+            "quot" => '"',
+            "apos" => '\'',
+            "" => {
+                decoded.push('&');
+                continue;
+            }
+            _ if name.starts_with("#x") || name.starts_with("#X") => char::from_u32(u32::from_str_radix(&name[2..], 16).ok()?)?,
+            _ if name.starts_with('#') => char::from_u32(name[1..].parse().ok()?)?,
+            // Unknown named references could hide anything, give up
+            _ => return None,
+        };
+        decoded.push(c);
+        rest = after.strip_prefix(';').unwrap_or(after);
+    }
+    decoded.push_str(rest);
+    Some(decoded)
+}
+
+/// Returns the URL if it is safe to link to: web and mail links, and links to pages of the app
+pub fn safe_url(url: &str) -> Option<String> {
+    let url = decode_entities(url.trim())?;
+    if url.chars().any(|c| c.is_control() || c.is_whitespace()) {
+        return None;
+    }
+    // A colon before any slash, query or fragment means the URL has a scheme
+    match url.find(|c: char| matches!(c, ':' | '/' | '?' | '#')) {
+        Some(i) if url[i..].starts_with(':') => {
+            let scheme = url[..i].to_ascii_lowercase();
+            matches!(scheme.as_str(), "http" | "https" | "mailto").then_some(url)
+        }
+        _ => Some(url),
+    }
+}
+
+struct Tag<'a> {
+    name: String,
+    closing: bool,
+    attributes: Vec<(String, &'a str)>,
+}
+
+/// Parse the tag at the start of `input`, which starts right after a `<`. Returns the tag and the remaining input.
+fn parse_tag(input: &str) -> Option<(Tag<'_>, &str)> {
+    let (closing, mut rest) = match input.strip_prefix('/') {
+        Some(rest) => (true, rest),
+        None => (false, input),
+    };
+    let name_end = rest.find(|c: char| !c.is_ascii_alphanumeric()).unwrap_or(rest.len());
+    if name_end == 0 {
+        return None;
+    }
+    let name = rest[..name_end].to_ascii_lowercase();
+    rest = &rest[name_end..];
+
+    let mut attributes = Vec::new();
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+        if let Some(rest) = rest.strip_prefix('>') {
+            return Some((Tag { name, closing, attributes }, rest));
+        }
+        let attr_end = rest.find(|c: char| c.is_whitespace() || matches!(c, '=' | '>' | '/')).filter(|i| *i > 0)?;
+        let attr_name = rest[..attr_end].to_ascii_lowercase();
+        rest = rest[attr_end..].trim_start();
+        let mut value = "";
+        if let Some(after) = rest.strip_prefix('=') {
+            let after = after.trim_start();
+            let (v, r) = match after.chars().next()? {
+                quote @ ('"' | '\'') => {
+                    let end = after[1..].find(quote)? + 1;
+                    (&after[1..end], &after[end + 1..])
+                }
+                _ => {
+                    let end = after.find(|c: char| c.is_whitespace() || c == '>').unwrap_or(after.len());
+                    after.split_at(end)
+                }
+            };
+            value = v;
+            rest = r;
+        }
+        attributes.push((attr_name, value));
+    }
+}
+
+/// Keep only the allowed tags and attributes of an HTML fragment, drop scripts and unsafe URLs, and close the tags left open
+pub fn sanitize_html(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut open_tags: Vec<String> = Vec::new();
+    let mut rest = input;
+
+    while !rest.is_empty() {
+        let Some(i) = rest.find('<') else {
+            output.push_str(&rest.replace('>', "&gt;"));
+            break;
+        };
+        output.push_str(&rest[..i].replace('>', "&gt;"));
+        rest = &rest[i + 1..];
+
+        if let Some(comment) = rest.strip_prefix("!--") {
+            rest = comment.find("-->").map(|end| &comment[end + 3..]).unwrap_or("");
+            continue;
+        }
+        let Some((tag, after)) = parse_tag(rest) else {
+            output.push_str("&lt;");
+            continue;
+        };
+        rest = after;
+
+        if DROPPED_TAGS.contains(&tag.name.as_str()) {
+            if !tag.closing {
+                let closing_tag = format!("</{}", tag.name);
+                rest = rest.to_ascii_lowercase().find(&closing_tag)
+                    .and_then(|end| rest[end..].find('>').map(|close| &rest[end + close + 1..]))
+                    .unwrap_or("");
+            }
+            continue;
+        }
+        let Some((_, allowed_attributes)) = ALLOWED_TAGS.iter().find(|(name, _)| *name == tag.name) else { continue };
+
+        if tag.closing {
+            if let Some(position) = open_tags.iter().rposition(|name| *name == tag.name) {
+                for name in open_tags.drain(position..).rev() {
+                    output.push_str(&format!("</{name}>"));
+                }
+            }
+            continue;
+        }
+
+        output.push('<');
+        output.push_str(&tag.name);
+        for (name, value) in &tag.attributes {
+            if !allowed_attributes.contains(&name.as_str()) {
+                continue;
+            }
+            let value = match name.as_str() {
+                "href" | "src" => match safe_url(value) {
+                    Some(url) => url,
+                    None => continue,
+                },
+                _ => decode_entities(value).unwrap_or_default(),
+            };
+            output.push_str(&format!(" {name}=\"{}\"", escape_html(&value)));
+        }
+        if tag.name == "a" {
+            output.push_str(" target=\"_blank\" rel=\"noopener noreferrer\"");
+        }
+        output.push('>');
+        if !VOID_TAGS.contains(&tag.name.as_str()) {
+            open_tags.push(tag.name);
+        }
+    }
+
+    for name in open_tags.into_iter().rev() {
+        output.push_str(&format!("</{name}>"));
+    }
+    output
+}
+
+/// Replace the inline Markdown syntax of an already escaped line: links, images, code, bold and italic
+fn markdown_inline(line: &str) -> String {
+    let mut output = String::with_capacity(line.len());
+    let mut rest = line;
+    let mut bold = false;
+    let mut italic = false;
+
+    while let Some(c) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix('`') {
+            if let Some(end) = after.find('`') {
+                output.push_str(&format!("<code>{}</code>", &after[..end]));
+                rest = &after[end + 1..];
+                continue;
+            }
+        }
+        let image = rest.starts_with("![");
+        if image || c == '[' {
+            let label_start = if image { 2 } else { 1 };
+            let link = rest.find("](").and_then(|middle| {
+                let end = rest[middle..].find(')')? + middle;
+                Some((&rest[label_start..middle], &rest[middle + 2..end], end))
+            });
+            if let Some((label, url, end)) = link {
+                match image {
+                    true => output.push_str(&format!("<img src=\"{url}\" alt=\"{label}\">")),
+                    false => output.push_str(&format!("<a href=\"{url}\">{}</a>", markdown_inline(label))),
+                }
+                rest = &rest[end + 1..];
+                continue;
+            }
+        }
+        if let Some(after) = rest.strip_prefix("**") {
+            output.push_str(if bold { "</strong>" } else { "<strong>" });
+            bold = !bold;
+            rest = after;
+            continue;
+        }
+        if c == '*' {
+            output.push_str(if italic { "</em>" } else { "<em>" });
+            italic = !italic;
+            rest = &rest[1..];
+            continue;
+        }
+        output.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    output
+}
+
+/// Convert the common subset of Markdown used in announcements (paragraphs, headings, lists, quotes, emphasis, links and images) to sanitized HTML
+pub fn markdown_to_html(input: &str) -> String {
+    enum Block { Paragraph, List(&'static str), Quote }
+
+    let mut output = String::new();
+    let mut block: Option<Block> = None;
+    let close = |block: &mut Option<Block>, output: &mut String| match block.take() {
+        Some(Block::Paragraph) => output.push_str("</p>"),
+        Some(Block::List(tag)) => output.push_str(&format!("</{tag}>")),
+        Some(Block::Quote) => output.push_str("</blockquote>"),
+        None => (),
+    };
+
+    for line in input.lines() {
+        let line = escape_html(line.trim());
+        if line.is_empty() {
+            close(&mut block, &mut output);
+            continue;
+        }
+        let heading = line.chars().take_while(|c| *c == '#').count();
+        if heading > 0 && line[heading..].starts_with(' ') {
+            close(&mut block, &mut output);
+            let tag = if heading == 1 { "h3" } else { "h4" };
+            output.push_str(&format!("<{tag}>{}</{tag}>", markdown_inline(line[heading..].trim())));
+            continue;
+        }
+        let ordered_item = line.find(". ").filter(|i| *i > 0 && line[..*i].chars().all(|c| c.is_ascii_digit())).map(|i| &line[i + 2..]);
+        let item = line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")).map(|item| ("ul", item)).or(ordered_item.map(|item| ("ol", item)));
+        if let Some((tag, item)) = item {
+            if !matches!(block, Some(Block::List(t)) if t == tag) {
+                close(&mut block, &mut output);
+                output.push_str(&format!("<{tag}>"));
+                block = Some(Block::List(tag));
+            }
+            output.push_str(&format!("<li>{}</li>", markdown_inline(item)));
+            continue;
+        }
+        if let Some(quote) = line.strip_prefix("&gt; ") {
+            if !matches!(block, Some(Block::Quote)) {
+                close(&mut block, &mut output);
+                output.push_str("<blockquote>");
+                block = Some(Block::Quote);
+            }
+            output.push_str(&markdown_inline(quote));
+            output.push_str("<br>");
+            continue;
+        }
+        match block {
+            Some(Block::Paragraph) => output.push_str("<br>"),
+            _ => {
+                close(&mut block, &mut output);
+                output.push_str("<p>");
+                block = Some(Block::Paragraph);
+            }
+        }
+        output.push_str(&markdown_inline(&line));
+    }
+    close(&mut block, &mut output);
+
+    sanitize_html(&output)
+}
+
+/// HTML of an image announcement, or `None` if its URL is unsafe
+pub fn image_html(src: &str, alt: &str) -> Option<String> {
+    let src = safe_url(src)?;
+    Some(format!("<img src=\"{}\" alt=\"{}\">", escape_html(&src), escape_html(alt)))
+}
+
+/// Whether sanitized HTML shows anything to the user
+pub fn has_content(html: &str) -> bool {
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag && !c.is_whitespace() => return true,
+            _ => (),
+        }
+    }
+    html.contains("<img")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_scripts() {
+        assert_eq!(sanitize_html("a<script>alert(1)</script>b"), "ab");
+        assert_eq!(sanitize_html("a<SCRIPT type=\"text/javascript\">alert(1)</ScRiPt>b"), "ab");
+        assert_eq!(sanitize_html("a<script>alert(1)"), "a");
+        assert_eq!(sanitize_html("<svg><script>alert(1)</script></svg>"), "");
+    }
+
+    #[test]
+    fn drops_event_handlers() {
+        assert_eq!(sanitize_html("<b onclick=\"alert(1)\">x</b>"), "<b>x</b>");
+        assert_eq!(sanitize_html("<img src=\"a.png\" onerror=alert(1)>"), "<img src=\"a.png\">");
+        assert_eq!(sanitize_html("<div onmouseover='alert(1)'>x</div>"), "x");
+    }
+
+    #[test]
+    fn drops_javascript_urls() {
+        let link = " target=\"_blank\" rel=\"noopener noreferrer\"";
+        assert_eq!(sanitize_html("<a href=\"javascript:alert(1)\">x</a>"), format!("<a{link}>x</a>"));
+        assert_eq!(sanitize_html("<a href=\" JavaScript:alert(1)\">x</a>"), format!("<a{link}>x</a>"));
+        assert_eq!(sanitize_html("<a href=\"java&#x09;script:alert(1)\">x</a>"), format!("<a{link}>x</a>"));
+        assert_eq!(sanitize_html("<a href=\"&#106;avascript:alert(1)\">x</a>"), format!("<a{link}>x</a>"));
+        assert_eq!(sanitize_html("<img src=\"data:image/svg+xml,x\">"), "<img>");
+        assert_eq!(sanitize_html("<a href=\"https://insa-rouen.fr\">x</a>"), format!("<a href=\"https://insa-rouen.fr\"{link}>x</a>"));
+        assert_eq!(safe_url("/agenda"), Some(String::from("/agenda")));
+        assert_eq!(safe_url("mailto:a@b.fr"), Some(String::from("mailto:a@b.fr")));
+    }
+
+    #[test]
+    fn escapes_and_closes() {
+        assert_eq!(sanitize_html("<a title='\"><script>'>x"), "<a title=\"&quot;&gt;&lt;script&gt;\" target=\"_blank\" rel=\"noopener noreferrer\">x</a>");
+        assert_eq!(sanitize_html("<b><i>x</b>"), "<b><i>x</i></b>");
+        assert_eq!(sanitize_html("1 < 2 > 0"), "1 &lt; 2 &gt; 0");
+        assert_eq!(sanitize_html("a<!-- <script> -->b"), "ab");
+    }
+}
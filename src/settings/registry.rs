@@ -0,0 +1,240 @@
+//! Declarative registry of the settings of the settings page.
+//!
+//! Settings are declared once with the [`settings!`] macro, with their type, the key under which they are saved in the local storage and their default value.
+//! Saved and imported values are validated by [`SettingValue::parse`], and components can [subscribe](SettingStore::subscribe) to changes.
+//! Changes made on this device are also recorded to be [synced](SETTINGS_SYNC) with the server.
+
+use std::sync::RwLock;
+use crate::prelude::*;
+
+/// A type that can be the value of a setting
+pub trait SettingValue: Clone + PartialEq + Send + Sync + 'static {
+    /// Parse a saved or imported value, returning `None` if it isn't valid
+    fn parse(value: &str) -> Option<Self>;
+
+    fn to_value_string(&self) -> String;
+
+    /// Apply the new value to the page when it changes
+    fn apply(&self) {}
+}
+
+/// Implement [`SettingValue`] for an enum whose variants are saved as fixed strings.
+/// The variants are also listed in `ALL`, in the order of their selector.
+macro_rules! choice_setting {
+    ($ty:ident { $($variant:ident => $value:literal),* $(,)? } $(, apply = $apply:path)?) => {
+        impl $ty {
+            pub const ALL: &'static [$ty] = &[$($ty::$variant),*];
+        }
+
+        impl SettingValue for $ty {
+            fn parse(value: &str) -> Option<Self> {
+                match value {
+                    $($value => Some($ty::$variant),)*
+                    _ => None,
+                }
+            }
+
+            fn to_value_string(&self) -> String {
+                match self {
+                    $($ty::$variant => $value,)*
+                }.to_string()
+            }
+
+            $(fn apply(&self) {
+                $apply(self)
+            })?
+        }
+    };
+}
+
+/// Declare the [`SettingStore`], with the name, type, storage key and default value of each setting
+macro_rules! settings {
+    ($($(#[$doc:meta])* $name:ident: $ty:ty = $key:literal, $default:expr;)*) => {
+        pub struct SettingStore {
+            $($(#[$doc])* pub $name: Setting<$ty>,)*
+        }
+
+        impl SettingStore {
+            fn load() -> SettingStore {
+                SettingStore {
+                    $($name: Setting::load(stringify!($name), $key, || $default),)*
+                }
+            }
+
+            /// Every setting, for the operations that don't depend on their type
+            pub fn all(&self) -> Vec<&dyn AnySetting> {
+                vec![$(&self.$name),*]
+            }
+        }
+    };
+}
+
+pub struct Setting<T: SettingValue> {
+    name: &'static str,
+    /// Key of the value in the local storage
+    key: &'static str,
+    value: RwLock<T>,
+}
+
+impl<T: SettingValue> Setting<T> {
+    /// Read the saved value of a setting, falling back to the default if there is none or if it is invalid
+    pub fn load(name: &'static str, key: &'static str, default: fn() -> T) -> Setting<T> {
+        let storage = window().local_storage().unwrap().unwrap();
+        let saved = storage.get_item(key).unwrap().and_then(|value| T::parse(&value));
+        Setting {
+            name,
+            key,
+            value: RwLock::new(saved.unwrap_or_else(default)),
+        }
+    }
+
+    pub fn get(&self) -> T {
+        self.value.read().unwrap().clone()
+    }
+
+    /// Change the value, save it and notify the subscribers. Returns `false` if the value didn't change.
+    fn store(&self, value: T) -> bool {
+        if *self.value.read().unwrap() == value {
+            return false;
+        }
+        let storage = window().local_storage().unwrap().unwrap();
+        storage.set_item(self.key, &value.to_value_string()).unwrap();
+        value.apply();
+        *self.value.write().unwrap() = value;
+        notify(self.name);
+        true
+    }
+
+    pub fn set(&self, value: T) {
+        if self.store(value) {
+            SETTINGS_SYNC.record(self.name);
+        }
+    }
+}
+
+/// Operations on a setting that don't depend on the type of its value
+pub trait AnySetting {
+    /// Name of the setting in exported settings and change notifications
+    fn name(&self) -> &'static str;
+    fn value_string(&self) -> String;
+    fn is_valid(&self, value: &str) -> bool;
+    /// Set the value from its string representation. Returns `false` if it is invalid.
+    fn set_string(&self, value: &str) -> bool;
+    /// Set a value received from the server, which isn't synced back
+    fn set_synced(&self, value: &str) -> bool;
+    /// Whether the value was chosen by the user rather than being the default
+    fn is_saved(&self) -> bool;
+    /// Apply the current value to the page
+    fn apply(&self);
+}
+
+impl<T: SettingValue> AnySetting for Setting<T> {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn value_string(&self) -> String {
+        self.get().to_value_string()
+    }
+
+    fn is_valid(&self, value: &str) -> bool {
+        T::parse(value).is_some()
+    }
+
+    fn set_string(&self, value: &str) -> bool {
+        match T::parse(value) {
+            Some(value) => {
+                self.set(value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn set_synced(&self, value: &str) -> bool {
+        match T::parse(value) {
+            Some(value) => {
+                self.store(value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn is_saved(&self) -> bool {
+        let storage = window().local_storage().unwrap().unwrap();
+        matches!(storage.get_item(self.key), Ok(Some(_)))
+    }
+
+    fn apply(&self) {
+        self.get().apply()
+    }
+}
+
+thread_local! {
+    static SUBSCRIBERS: RefCell<Vec<Callback<&'static str>>> = RefCell::new(Vec::new());
+}
+
+fn notify(name: &'static str) {
+    // Subscribers are cloned so that they can subscribe again while being notified
+    let subscribers = SUBSCRIBERS.with(|s| s.borrow().clone());
+    for subscriber in subscribers {
+        subscriber.emit(name);
+    }
+}
+
+/// Values of all the settings, used to revert changes
+pub type SettingsSnapshot = Vec<(&'static str, String)>;
+
+impl SettingStore {
+    /// Call `callback` with the name of every setting that changes
+    pub fn subscribe(&self, callback: Callback<&'static str>) {
+        SUBSCRIBERS.with(|s| s.borrow_mut().push(callback));
+    }
+
+    /// Apply the saved values to the page, as they are only applied when they change otherwise
+    pub fn apply_all(&self) {
+        for setting in self.all() {
+            setting.apply();
+        }
+    }
+
+    pub fn snapshot(&self) -> SettingsSnapshot {
+        self.all().into_iter().map(|setting| (setting.name(), setting.value_string())).collect()
+    }
+
+    pub fn restore(&self, snapshot: &SettingsSnapshot) {
+        for setting in self.all() {
+            if let Some((_, value)) = snapshot.iter().find(|(name, _)| *name == setting.name()) {
+                setting.set_string(value);
+            }
+        }
+    }
+
+    /// Export the settings as a JSON object mapping the name of each setting to its value
+    pub fn export(&self) -> String {
+        let settings = self.all().into_iter()
+            .map(|setting| (setting.name().to_string(), serde_json::Value::String(setting.value_string())))
+            .collect::<serde_json::Map<_, _>>();
+        serde_json::to_string_pretty(&settings).unwrap()
+    }
+
+    /// Import settings exported by [`SettingStore::export`], possibly on another device.
+    /// Unknown settings are ignored. Nothing is changed if a value is invalid.
+    /// Returns the number of imported settings.
+    pub fn import(&self, json: &str) -> Result<usize, String> {
+        let values: serde_json::Map<String, serde_json::Value> = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        let mut imported = Vec::new();
+        for setting in self.all() {
+            let Some(value) = values.get(setting.name()) else { continue };
+            match value.as_str() {
+                Some(value) if setting.is_valid(value) => imported.push((setting, value)),
+                _ => return Err(tf("Valeur invalide pour le paramètre {name}", &[("name", &setting.name())])),
+            }
+        }
+        for (setting, value) in &imported {
+            setting.set_string(value);
+        }
+        Ok(imported.len())
+    }
+}
@@ -1,110 +1,103 @@
 use crate::prelude::*;
 
-lazy_static::lazy_static!{
-    pub static ref SETTINGS: SettingStore = {
-        let local_storage = window().local_storage().unwrap().unwrap();
-        let theme = match local_storage.get_item("setting-theme").unwrap() {
-            Some(theme) if theme == "dark" => 0,
-            Some(theme) if theme == "light" => 1,
-            _ => 2,
-        };
-        let lang = match local_storage.get_item("setting-lang").unwrap() {
-            Some(lang) if lang == "french" => 0,
-            Some(lang) if lang == "english" => 1,
-            Some(lang) => {alert(format!("Invalid language {lang}")); 0},
-            None => {
-                let languages = window().navigator().languages();
-                let mut lang = None;
-                for language in languages.iter() {
-                    if let Some(language) = language.as_string() {
-                        if language == "fr" || language.starts_with("fr-") {
-                            lang = Some(0);
-                            break;
-                        } else if language == "en" || language.starts_with("en-") {
-                            lang = Some(1);
-                            break;
-                        }
-                    }
-                }
+#[macro_use]
+mod registry;
+pub use registry::*;
+mod sync;
+pub use sync::*;
 
-                lang.unwrap_or(0)
-            },
-        };
+settings! {
+    theme: Theme = "setting-theme", Theme::System;
+    /// Language of the interface
+    lang: Locale = "setting-lang", Locale::from_browser();
+    hour_cycle: HourCycle = "setting-hour-cycle", HourCycle::Auto;
+    week_start: WeekStart = "setting-week-start", WeekStart::Auto;
+    time_zone: TimeZoneSetting = "setting-time-zone", TimeZoneSetting::Fixed(CAMPUS_TIME_ZONE);
+    text_size: TextSize = "setting-text-size", TextSize::Normal;
+    motion: Motion = "setting-motion", Motion::Auto;
+    contrast: Contrast = "setting-contrast", Contrast::Normal;
+}
 
-        SettingStore {
-            theme: AtomicUsize::new(theme),
-            lang: AtomicUsize::new(lang),
-        }
-    };
+lazy_static::lazy_static!{
+    pub static ref SETTINGS: SettingStore = SettingStore::load();
 }
 
+impl SettingStore {
+    /// Identifier of the language of the interface, such as `fr` or `zh`
+    pub fn locale(&self) -> &'static str {
+        self.lang.get().0
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
 pub enum Theme {
     Dark = 0,
     Light,
     System,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
-pub enum Lang {
-    French = 0,
-    English,
-}
+choice_setting!(Theme { Dark => "dark", Light => "light", System => "system" }, apply = apply_theme);
 
-pub struct SettingStore {
-    theme: AtomicUsize,
-    lang: AtomicUsize,
-}
-
-impl SettingStore {
-    pub fn theme(&self) -> Theme {
-        match self.theme.load(Ordering::Relaxed) {
-            0 => Theme::Dark,
-            1 => Theme::Light,
-            2 => Theme::System,
-            _ => unreachable!(),
+fn apply_theme(theme: &Theme) {
+    let window = window();
+    let html = window.doc().first_element_child().unwrap();
+    let storage = window.local_storage().unwrap().unwrap();
+    match theme {
+        Theme::System => storage.set_item("auto-theme", "true").unwrap(),
+        theme => {
+            storage.set_item("auto-theme", "false").unwrap();
+            html.set_attribute("data-theme", &theme.to_value_string()).unwrap();
         }
     }
+    // The text color of events depends on the background of the page
+    COLORS_CHANGED.store(true, Ordering::Relaxed);
+}
 
-    fn set_theme(&self, theme: usize) {
-        self.theme.store(theme, Ordering::Relaxed);
+choice_setting!(HourCycle { Auto => "auto", H24 => "24", H12 => "12" });
+choice_setting!(WeekStart { Auto => "auto", Monday => "monday", Sunday => "sunday" });
+choice_setting!(TextSize { Small => "small", Normal => "normal", Large => "large", ExtraLarge => "extra-large" }, apply = apply_text_size);
+choice_setting!(Motion { Auto => "auto", Reduced => "reduced", Full => "full" }, apply = apply_motion);
+choice_setting!(Contrast { Normal => "normal", High => "high" }, apply = apply_contrast);
 
-        let theme = match theme {
-            0 => "dark",
-            1 => "light",
-            2 => "system",
-            _ => unreachable!(),
-        };
+/// A locale that has a catalog
+#[derive(Clone, Copy, PartialEq)]
+pub struct Locale(pub &'static str);
 
-        let storage = window().local_storage().unwrap().unwrap();
-        storage.set_item("setting-theme", theme).unwrap();
+impl Locale {
+    fn from_browser() -> Locale {
+        let languages = window().navigator().languages().iter().filter_map(|l| l.as_string()).collect::<Vec<_>>();
+        Locale(negotiate_locale(languages).unwrap_or(DEFAULT_LOCALE))
     }
+}
 
-    pub fn lang(&self) -> Lang {
-        match self.lang.load(Ordering::Relaxed) {
-            0 => Lang::French,
-            1 => Lang::English,
-            _ => unreachable!(),
+impl SettingValue for Locale {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            // Values saved by previous versions
+            "french" => Some(Locale("fr")),
+            "english" => Some(Locale("en")),
+            value => negotiate_locale(Some(value.to_string())).map(Locale),
         }
     }
 
-    pub fn locale(&self) -> &str {
-        match self.lang() {
-            Lang::French => "fr",
-            Lang::English => "en",
-        }
+    fn to_value_string(&self) -> String {
+        self.0.to_string()
     }
+}
 
-    fn set_lang(&self, lang: usize) {
-        self.lang.store(lang, Ordering::Relaxed);
-
-        let lang = match lang {
-            0 => "french",
-            1 => "english",
-            _ => unreachable!(),
-        };
+impl SettingValue for TimeZoneSetting {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "auto" => Some(TimeZoneSetting::Auto),
+            value => value.parse().ok().map(TimeZoneSetting::Fixed),
+        }
+    }
 
-        let storage = window().local_storage().unwrap().unwrap();
-        storage.set_item("setting-lang", lang).unwrap();
+    fn to_value_string(&self) -> String {
+        match self {
+            TimeZoneSetting::Auto => String::from("auto"),
+            TimeZoneSetting::Fixed(tz) => tz.name().to_string(),
+        }
     }
 }
 
@@ -113,23 +106,86 @@ pub enum Msg {
     Cancel,
     ThemeChange(usize),
     LogOut,
-    LanguageChange(usize),
+    LanguageChange(web_sys::Event),
+    HourCycleChange(usize),
+    WeekStartChange(usize),
+    TimeZoneChange(web_sys::Event),
+    TextSizeChange(usize),
+    MotionChange(usize),
+    HighContrastChange(bool),
+    ExportSchedule,
+    ExportSettings,
+    ImportSettings(web_sys::Event),
+    SettingsImported(Result<usize, String>),
+    EditGroups,
+    CloseGroupEditor,
+    ExportData,
+    /// Ask for a confirmation before deleting the account
+    Delete,
+    CancelDeletion,
+    ConfirmDeletion,
+    ImportCalendar(web_sys::Event),
+    RemoveImportedCalendar(MouseEvent),
+    PersonalSyncChange(bool),
+    RemindersEnabledChange(bool),
+    ReminderModeChange(usize),
+    ReminderDelayChange(usize),
+    ReminderCourseChange(String, bool),
+    FriendsCacheCapacityChange(usize),
+    FriendsCacheTtlChange(usize),
+    PushAnnouncementsChange(bool),
+    PushFriendRequestsChange(bool),
+    /// The push subscription could not be updated, so the previous settings are restored
+    PushSettingsFailed(PushSettings),
 }
 
+/// Choices for the number of minutes between a reminder and its class
+const REMINDER_DELAYS: [u32; 4] = [5, 10, 15, 30];
+
+/// Choices for the number of cached friend schedules
+const FRIENDS_CACHE_CAPACITIES: [usize; 4] = [2, 5, 10, 20];
+
+/// Choices for the time after which a friend schedule is fetched again, in hours
+const FRIENDS_CACHE_TTLS: [u64; 4] = [1, 5, 12, 24];
+
 #[derive(Properties, Clone)]
 pub struct SettingsProps {
     pub app_link: Scope<App>,
     pub user_info: Rc<Option<UserInfo>>,
+    pub events: Rc<Vec<RawEvent>>,
+    pub imported_sources: Vec<String>,
+    /// Whether changes are waiting to be synced with the server
+    pub syncing: bool,
 }
 
 impl PartialEq for SettingsProps {
     fn eq(&self, other: &Self) -> bool { 
-        self.user_info == other.user_info
+        self.user_info == other.user_info && self.events == other.events && self.imported_sources == other.imported_sources && self.syncing == other.syncing
     }
 }
 
 pub struct SettingsPage {
-    clone_storage: SettingStore,
+    /// Values of the settings when the page was opened, restored on cancel
+    snapshot: SettingsSnapshot,
+    reminders: ReminderSettings,
+    push: PushSettings,
+    editing_groups: bool,
+    confirming_deletion: bool,
+    deleting: bool,
+}
+
+impl SettingsPage {
+    /// Save the push settings and update the subscription, restoring `previous` if that fails
+    fn update_push_subscription(&self, ctx: &Context<Self>, previous: PushSettings) {
+        self.push.save();
+        let push = self.push.clone();
+        let link = ctx.link().clone();
+        spawn_local(async move {
+            if !update_push_subscription(&push).await {
+                link.send_message(Msg::PushSettingsFailed(previous));
+            }
+        });
+    }
 }
 
 impl Component for SettingsPage {
@@ -138,48 +194,29 @@ impl Component for SettingsPage {
 
     fn create(_ctx: &Context<Self>) -> Self {
         Self {
-            clone_storage: SettingStore {
-                theme: AtomicUsize::new(SETTINGS.theme.load(Ordering::Relaxed)),
-                lang: AtomicUsize::new(SETTINGS.lang.load(Ordering::Relaxed)),
-            }
+            snapshot: SETTINGS.snapshot(),
+            reminders: ReminderSettings::load(),
+            push: PushSettings::load(),
+            editing_groups: false,
+            confirming_deletion: false,
+            deleting: false,
         }
     }
 
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
             Msg::Confirm => {
+                SETTINGS_SYNC.push(ctx.props().app_link.clone());
                 ctx.props().app_link.send_message(AppMsg::SetPage(Page::Agenda));
                 false
             }
             Msg::Cancel => {
                 ctx.props().app_link.send_message(AppMsg::SetPage(Page::Agenda));
-                SETTINGS.set_theme(self.clone_storage.theme.load(Ordering::Relaxed));
-                SETTINGS.set_lang(self.clone_storage.lang.load(Ordering::Relaxed));
+                SETTINGS.restore(&self.snapshot);
                 false
             }
             Msg::ThemeChange(v) => {
-                SETTINGS.set_theme(v);
-
-                let theme = match v {
-                    0 => "dark",
-                    1 => "light",
-                    2 => "system",
-                    _ => unreachable!(),
-                };
-
-                let window = window();
-                let doc = window.doc();
-                let html = doc.first_element_child().unwrap();
-                let storage = window.local_storage().unwrap().unwrap();
-
-                if theme == "system" {
-                    storage.set_item("auto-theme", "true").unwrap();
-                } else {
-                    storage.set_item("auto-theme", "false").unwrap();
-                    html.set_attribute("data-theme", theme).unwrap();
-                    storage.set_item("setting-theme", theme).unwrap();
-                }
-                                
+                SETTINGS.theme.set(Theme::ALL[v]);
                 true
             }
             Msg::LogOut => {
@@ -195,10 +232,239 @@ impl Component for SettingsPage {
                 window().location().replace("/login").unwrap();
                 false
             }
-            Msg::LanguageChange(v) => {
-                SETTINGS.set_lang(v);
+            Msg::LanguageChange(event) => {
+                let Some(select) = event.target().and_then(|t| t.dyn_into::<web_sys::HtmlSelectElement>().ok()) else { return false };
+                let Some(locale) = Locale::parse(&select.value()) else { return false };
+                SETTINGS.lang.set(locale);
+                true
+            }
+            Msg::HourCycleChange(v) => {
+                SETTINGS.hour_cycle.set(HourCycle::ALL[v]);
+                true
+            }
+            Msg::WeekStartChange(v) => {
+                SETTINGS.week_start.set(WeekStart::ALL[v]);
+                true
+            }
+            Msg::TimeZoneChange(event) => {
+                let Some(select) = event.target().and_then(|t| t.dyn_into::<web_sys::HtmlSelectElement>().ok()) else { return false };
+                let Some(time_zone) = TimeZoneSetting::parse(&select.value()) else { return false };
+                SETTINGS.time_zone.set(time_zone);
+                true
+            }
+            Msg::TextSizeChange(v) => {
+                SETTINGS.text_size.set(TextSize::ALL[v]);
+                true
+            }
+            Msg::MotionChange(v) => {
+                SETTINGS.motion.set(Motion::ALL[v]);
+                true
+            }
+            Msg::HighContrastChange(enabled) => {
+                SETTINGS.contrast.set(if enabled { Contrast::High } else { Contrast::Normal });
+                true
+            }
+            Msg::ExportSchedule => {
+                let ics = events_to_ics(ctx.props().events.iter());
+                download_ics("insagenda.ics", &ics);
+                false
+            }
+            Msg::ExportSettings => {
+                if let Err(e) = download_file("insagenda-settings.json", "application/json", &SETTINGS.export()) {
+                    sentry_report(e);
+                    alert(t("Impossible d'exporter les paramètres."));
+                }
+                false
+            }
+            Msg::ImportSettings(event) => {
+                let input = event.target().unwrap().dyn_into::<HtmlInputElement>().unwrap();
+                let Some(file) = input.files().and_then(|files| files.get(0)) else { return false };
+                input.set_value("");
+
+                let link = ctx.link().clone();
+                spawn_local(async move {
+                    let result = match JsFuture::from(file.text()).await {
+                        Ok(content) => SETTINGS.import(&content.as_string().unwrap_or_default()),
+                        Err(e) => Err(format!("{e:?}")),
+                    };
+                    link.send_message(Msg::SettingsImported(result));
+                });
+                false
+            }
+            Msg::SettingsImported(Ok(count)) => {
+                // Imported settings are kept on cancel
+                self.snapshot = SETTINGS.snapshot();
+                alert_no_reporting(tn("{n} paramètre importé.", "{n} paramètres importés.", count as u64, &[]));
+                true
+            }
+            Msg::SettingsImported(Err(e)) => {
+                alert_no_reporting(format!("{} ({e})", t("Impossible d'importer ces paramètres.")));
+                false
+            }
+            Msg::EditGroups => {
+                self.editing_groups = true;
+                true
+            }
+            Msg::CloseGroupEditor => {
+                self.editing_groups = false;
+                true
+            }
+            Msg::ExportData => {
+                spawn_local(async move {
+                    let (archive, server_error) = export_data().await;
+                    if let Err(e) = download_bytes("insagenda-data.zip", "application/zip", &archive) {
+                        sentry_report(e);
+                        alert(t("Impossible d'exporter vos données."));
+                        return;
+                    }
+                    if let Some(e) = server_error {
+                        e.handle_api_error();
+                        alert(t("Les données enregistrées sur nos serveurs n'ont pas pu être exportées. L'archive contient seulement les données de cet appareil."));
+                    }
+                });
+                false
+            }
+            Msg::Delete => {
+                self.confirming_deletion = true;
+                true
+            }
+            Msg::CancelDeletion => {
+                self.confirming_deletion = false;
+                self.deleting = false;
+                true
+            }
+            Msg::ConfirmDeletion => {
+                if self.deleting {
+                    return false;
+                }
+                self.deleting = true;
+                let link = ctx.link().clone();
+                spawn_local(async move {
+                    match delete_account().await {
+                        Ok(()) => window().location().replace("/login").unwrap(),
+                        Err(e) => {
+                            e.handle_api_error();
+                            link.send_message(Msg::CancelDeletion);
+                        }
+                    }
+                });
+                true
+            }
+            Msg::ImportCalendar(event) => {
+                let input = event.target().unwrap().dyn_into::<HtmlInputElement>().unwrap();
+                let Some(file) = input.files().and_then(|files| files.get(0)) else { return false };
+                input.set_value("");
+
+                let app_link = ctx.props().app_link.clone();
+                spawn_local(async move {
+                    let source = file.name();
+                    let content = match JsFuture::from(file.text()).await {
+                        Ok(content) => content.as_string().unwrap_or_default(),
+                        Err(e) => {
+                            sentry_report(e);
+                            alert_no_reporting(t("Impossible de lire ce calendrier."));
+                            return;
+                        }
+                    };
+                    let (events, unsupported) = match parse_ics(&content, &source) {
+                        Ok(parsed) => parsed,
+                        Err(e) => {
+                            alert_no_reporting(format!("{} ({e})", t("Impossible de lire ce calendrier.")));
+                            return;
+                        }
+                    };
+                    if unsupported > 0 {
+                        alert_no_reporting(tn("Un événement a été ignoré car sa répétition n'est pas prise en charge.", "{n} événements ont été ignorés car leur répétition n'est pas prise en charge.", unsupported as u64, &[]));
+                    }
+                    match events.is_empty() {
+                        true if unsupported == 0 => alert_no_reporting(t("Aucun événement n'a été trouvé dans ce calendrier.")),
+                        true => (),
+                        false => app_link.send_message(AppMsg::ImportCalendar { source, events }),
+                    }
+                });
+                false
+            }
+            Msg::RemindersEnabledChange(true) => {
+                let link = ctx.link().clone();
+                let events = Rc::clone(&ctx.props().events);
+                spawn_local(async move {
+                    if !request_notification_permission().await {
+                        alert_no_reporting(t("Les notifications ont été refusées par le navigateur."));
+                        link.send_message(Msg::RemindersEnabledChange(false));
+                        return;
+                    }
+                    let mut reminders = ReminderSettings::load();
+                    reminders.enabled = true;
+                    reminders.save();
+                    schedule_reminders(events);
+                });
+                self.reminders.enabled = true;
+                true
+            }
+            Msg::RemindersEnabledChange(false) => {
+                self.reminders.enabled = false;
+                self.reminders.save();
+                schedule_reminders(Rc::clone(&ctx.props().events));
+                true
+            }
+            Msg::ReminderModeChange(v) => {
+                self.reminders.mode = match v {
+                    0 => ReminderMode::AllClasses,
+                    1 => ReminderMode::FirstOfDay,
+                    _ => ReminderMode::SelectedCourses,
+                };
+                self.reminders.save();
+                schedule_reminders(Rc::clone(&ctx.props().events));
+                true
+            }
+            Msg::ReminderDelayChange(v) => {
+                self.reminders.minutes_before = REMINDER_DELAYS[v];
+                self.reminders.save();
+                schedule_reminders(Rc::clone(&ctx.props().events));
+                true
+            }
+            Msg::ReminderCourseChange(summary, applies) => {
+                self.reminders.set_applies_to(summary, applies);
+                self.reminders.save();
+                schedule_reminders(Rc::clone(&ctx.props().events));
+                true
+            }
+            Msg::PushAnnouncementsChange(enabled) => {
+                let previous = self.push.clone();
+                self.push.announcements = enabled;
+                self.update_push_subscription(ctx, previous);
+                true
+            }
+            Msg::PushFriendRequestsChange(enabled) => {
+                let previous = self.push.clone();
+                self.push.friend_requests = enabled;
+                self.update_push_subscription(ctx, previous);
+                true
+            }
+            Msg::PushSettingsFailed(previous) => {
+                previous.save();
+                self.push = previous;
+                true
+            }
+            Msg::FriendsCacheCapacityChange(v) => {
+                FriendsEvents::set_capacity(FRIENDS_CACHE_CAPACITIES[v]);
+                true
+            }
+            Msg::FriendsCacheTtlChange(v) => {
+                FriendsEvents::set_ttl(FRIENDS_CACHE_TTLS[v] * 3600);
                 true
             }
+            Msg::PersonalSyncChange(enabled) => {
+                ctx.props().app_link.send_message(AppMsg::SetPersonalEventsSync(enabled));
+                false
+            }
+            Msg::RemoveImportedCalendar(event) => {
+                let target = event.target().unwrap();
+                let el = target.dyn_into::<web_sys::Element>().unwrap();
+                let source = el.get_attribute("data-source").unwrap();
+                ctx.props().app_link.send_message(AppMsg::RemoveImportedCalendar(source));
+                false
+            }
         }
     }
 
@@ -209,18 +475,153 @@ impl Component for SettingsPage {
             groups = user_info.groups.groups().iter().map(|group| group.to_string()).collect::<Vec<_>>();
         }
         let group_iter = groups.into_iter();
+        let editing_groups = self.editing_groups;
+        let group_editor = match editing_groups {
+            true => html! {
+                <GroupEditor
+                    app_link={ctx.props().app_link.clone()}
+                    events={Rc::clone(&ctx.props().events)}
+                    on_close={ctx.link().callback(|_| Msg::CloseGroupEditor)} />
+            },
+            false => html!(),
+        };
+        let has_imported = !ctx.props().imported_sources.is_empty();
+        let imported_name_iter = ctx.props().imported_sources.clone().into_iter();
+        let imported_source_iter = ctx.props().imported_sources.clone().into_iter();
 
         let theme_glider_selector = html! {
             <GliderSelector
                 values = { vec![t("Sombre"), t("Clair"), t("Système")] }
                 on_change = { ctx.link().callback(Msg::ThemeChange) }
-                selected = { SETTINGS.theme() as usize } />
+                selected = { SETTINGS.theme.get() as usize }
+                labelled_by = { Some("setting-theme-title") } />
+        };
+        let current_locale = SETTINGS.locale();
+        let language_selector = html! {
+            <select class="settings-select" aria-labelledby="setting-lang-title" onchange={ctx.link().callback(Msg::LanguageChange)}>
+                { for LOCALES.iter().map(|(locale, name)| html! {
+                    <option value={*locale} selected={*locale == current_locale}>{name}</option>
+                }) }
+            </select>
+        };
+
+        let hour_cycle_selector = html! {
+            <GliderSelector
+                values = { vec![t("Automatique"), "24 h", "12 h"] }
+                on_change = { ctx.link().callback(Msg::HourCycleChange) }
+                selected = { SETTINGS.hour_cycle.get() as usize }
+                labelled_by = { Some("setting-hour-cycle-title") } />
+        };
+        let week_start_selector = html! {
+            <GliderSelector
+                values = { vec![t("Automatique"), t("Lundi"), t("Dimanche")] }
+                on_change = { ctx.link().callback(Msg::WeekStartChange) }
+                selected = { SETTINGS.week_start.get() as usize }
+                labelled_by = { Some("setting-week-start-title") } />
+        };
+        let current_time_zone = SETTINGS.time_zone.get();
+        let auto_time_zone_label = match browser_time_zone() {
+            Some(tz) => tf("Automatique ({zone})", &[("zone", &tz.name())]),
+            None => t("Automatique").to_string(),
+        };
+        let time_zone_selector = html! {
+            <select class="settings-select" aria-labelledby="setting-time-zone-title" onchange={ctx.link().callback(Msg::TimeZoneChange)}>
+                <option value="auto" selected={current_time_zone == TimeZoneSetting::Auto}>{auto_time_zone_label}</option>
+                { for chrono_tz::TZ_VARIANTS.iter().map(|tz| html! {
+                    <option value={tz.name()} selected={current_time_zone == TimeZoneSetting::Fixed(*tz)}>{tz.name()}</option>
+                }) }
+            </select>
+        };
+
+        let text_size_selector = html! {
+            <GliderSelector
+                values = { vec![t("Petit"), t("Normal"), t("Grand"), t("Très grand")] }
+                on_change = { ctx.link().callback(Msg::TextSizeChange) }
+                selected = { SETTINGS.text_size.get() as usize }
+                labelled_by = { Some("setting-text-size-title") } />
+        };
+        let motion_selector = html! {
+            <GliderSelector
+                values = { vec![t("Automatique"), t("Réduites"), t("Activées")] }
+                on_change = { ctx.link().callback(Msg::MotionChange) }
+                selected = { SETTINGS.motion.get() as usize }
+                labelled_by = { Some("setting-motion-title") } />
+        };
+        let high_contrast_checkbox = html! {
+            <Checkbox
+                message={t("Contraste élevé")}
+                checked={high_contrast()}
+                onchange={ctx.link().callback(Msg::HighContrastChange)} />
+        };
+
+        let reminders_supported = reminders_supported();
+        let reminders_enabled = self.reminders.enabled && reminders_supported;
+        let reminders_checkbox = html! {
+            <Checkbox
+                message={t("Me rappeler mes cours")}
+                checked={reminders_enabled}
+                onchange={ctx.link().callback(Msg::RemindersEnabledChange)} />
+        };
+        let reminder_mode_selector = html! {
+            <GliderSelector
+                values = { vec![t("Tous"), t("Premier du jour"), t("Au choix")] }
+                on_change = { ctx.link().callback(Msg::ReminderModeChange) }
+                selected = { self.reminders.mode as usize }
+                labelled_by = { Some("setting-reminder-mode-title") } />
         };
-        let language_glider_selector = html! {
+        let reminder_delay_selector = html! {
             <GliderSelector
-                values = { vec!["Français", "English"] }
-                on_change = { ctx.link().callback(Msg::LanguageChange) }
-                selected = { SETTINGS.lang() as usize } />
+                values = { vec!["5 min", "10 min", "15 min", "30 min"] }
+                on_change = { ctx.link().callback(Msg::ReminderDelayChange) }
+                selected = { REMINDER_DELAYS.iter().position(|d| *d == self.reminders.minutes_before).unwrap_or(1) }
+                labelled_by = { Some("setting-reminder-delay-title") } />
+        };
+        let mut courses = ctx.props().events.iter().map(|e| e.summary.clone()).collect::<Vec<_>>();
+        courses.sort();
+        courses.dedup();
+        let reminder_courses = courses.into_iter().map(|summary| {
+            let checked = self.reminders.applies_to(&summary);
+            let summary2 = summary.clone();
+            html! {
+                <Checkbox message={summary} checked={checked} onchange={ctx.link().callback(move |applies| Msg::ReminderCourseChange(summary2.clone(), applies))} />
+            }
+        }).collect::<Html>();
+
+        let push_announcements_checkbox = html! {
+            <Checkbox
+                message={t("Annonces")}
+                checked={self.push.announcements}
+                onchange={ctx.link().callback(Msg::PushAnnouncementsChange)} />
+        };
+        let push_friend_requests_checkbox = html! {
+            <Checkbox
+                message={t("Demandes d'ami")}
+                checked={self.push.friend_requests}
+                onchange={ctx.link().callback(Msg::PushFriendRequestsChange)} />
+        };
+
+        let capacity = FriendsEvents::capacity();
+        let friends_cache_capacity_selector = html! {
+            <GliderSelector
+                values = { vec!["2", "5", "10", "20"] }
+                on_change = { ctx.link().callback(Msg::FriendsCacheCapacityChange) }
+                selected = { FRIENDS_CACHE_CAPACITIES.iter().position(|c| *c >= capacity).unwrap_or(FRIENDS_CACHE_CAPACITIES.len() - 1) }
+                labelled_by = { Some("setting-friends-cache-capacity-title") } />
+        };
+        let ttl = FriendsEvents::ttl();
+        let friends_cache_ttl_selector = html! {
+            <GliderSelector
+                values = { vec!["1h", "5h", "12h", "24h"] }
+                on_change = { ctx.link().callback(Msg::FriendsCacheTtlChange) }
+                selected = { FRIENDS_CACHE_TTLS.iter().position(|h| h * 3600 >= ttl).unwrap_or(FRIENDS_CACHE_TTLS.len() - 1) }
+                labelled_by = { Some("setting-friends-cache-ttl-title") } />
+        };
+
+        let personal_sync_checkbox = html! {
+            <Checkbox
+                message={t("Synchroniser mes événements personnels avec mon compte")}
+                checked={PersonalEvents::sync_enabled()}
+                onchange={ctx.link().callback(Msg::PersonalSyncChange)} />
         };
 
         template_html!(
@@ -228,7 +629,19 @@ impl Component for SettingsPage {
             onclick_logout = {ctx.link().callback(move |_| Msg::LogOut)},
             onclick_confirm = {ctx.link().callback(move |_| Msg::Confirm)},
             onclick_delete = {ctx.link().callback(move |_| Msg::Delete)},
+            onclick_change_group = {ctx.link().callback(move |_| Msg::EditGroups)},
+            onclick_cancel_deletion = {ctx.link().callback(move |_| Msg::CancelDeletion)},
+            onclick_confirm_deletion = {ctx.link().callback(move |_| Msg::ConfirmDeletion)},
+            onclick_export_data = {ctx.link().callback(move |_| Msg::ExportData)},
+            confirming_deletion = {self.confirming_deletion},
+            deleting = {self.deleting},
             onclick_cancel = {ctx.link().callback(move |_| Msg::Cancel)},
+            onclick_export = {ctx.link().callback(move |_| Msg::ExportSchedule)},
+            syncing = {ctx.props().syncing},
+            onclick_export_settings = {ctx.link().callback(move |_| Msg::ExportSettings)},
+            onchange_import_settings = {ctx.link().callback(Msg::ImportSettings)},
+            onchange_import = {ctx.link().callback(Msg::ImportCalendar)},
+            onclick_remove_imported = {ctx.link().callback(Msg::RemoveImportedCalendar)},
             ...
         )
     }
@@ -0,0 +1,199 @@
+//! Sync of the settings, reminder preferences and course colors with the server, so that they follow the user across devices.
+//!
+//! Every synced value has a key, such as `theme` or `color:<course>`, and the time of its last change.
+//! When a key changed on several devices, the most recent change wins.
+//! Local changes are batched and pushed periodically by the agenda.
+//! Servers without the `settings` endpoint make sync unavailable until the app is reloaded, while colors keep being saved by the `colors` endpoint.
+
+use std::{collections::BTreeSet, sync::{Mutex, atomic::AtomicBool}};
+use crate::prelude::*;
+
+/// Prefix of the keys of course colors, followed by the name of the course
+const COLOR_PREFIX: &str = "color:";
+const REMINDERS_KEY: &str = "reminders";
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SyncedValue {
+    pub value: String,
+    /// Time of the change
+    pub modified: i64,
+}
+
+pub type SyncedSettings = HashMap<String, SyncedValue>;
+
+#[derive(Serialize, Deserialize, Default)]
+struct SyncState {
+    /// Time of the last change of each key
+    modified: HashMap<String, i64>,
+    /// Keys whose last change hasn't been sent to the server yet
+    pending: BTreeSet<String>,
+}
+
+lazy_static::lazy_static!{
+    pub static ref SETTINGS_SYNC: SettingsSync = SettingsSync::restore();
+}
+
+pub struct SettingsSync {
+    state: Mutex<SyncState>,
+    pushing: AtomicBool,
+    /// Whether the server doesn't support the sync of settings
+    unavailable: AtomicBool,
+}
+
+/// Current local value of a synced key
+fn current_value(key: &str) -> Option<String> {
+    if let Some(course) = key.strip_prefix(COLOR_PREFIX) {
+        return Some(COLORS.get(course));
+    }
+    if key == REMINDERS_KEY {
+        return Some(serde_json::to_string(&ReminderSettings::load()).unwrap());
+    }
+    SETTINGS.all().into_iter().find(|s| s.name() == key).map(|s| s.value_string())
+}
+
+/// Apply a value received from the server. Returns `false` if the key is unknown or the value invalid.
+fn apply_remote(key: &str, value: &str) -> bool {
+    if let Some(course) = key.strip_prefix(COLOR_PREFIX) {
+        COLORS.set_synced(course, value.to_string());
+        return true;
+    }
+    if key == REMINDERS_KEY {
+        let Ok(mut reminders) = serde_json::from_str::<ReminderSettings>(value) else { return false };
+        // Notifications must be allowed on each device, so enabling reminders isn't synced
+        reminders.enabled = ReminderSettings::load().enabled;
+        reminders.save_local();
+        return true;
+    }
+    SETTINGS.all().into_iter().find(|s| s.name() == key).map(|s| s.set_synced(value)).unwrap_or(false)
+}
+
+/// Keys that have a value on this device
+fn local_keys() -> Vec<String> {
+    let mut keys = COLORS.courses().into_iter().map(|course| format!("{COLOR_PREFIX}{course}")).collect::<Vec<_>>();
+    let local_storage = window().local_storage().unwrap().unwrap();
+    if let Ok(Some(_)) = local_storage.get("reminder_settings") {
+        keys.push(REMINDERS_KEY.to_string());
+    }
+    keys.extend(SETTINGS.all().into_iter().filter(|s| s.is_saved()).map(|s| s.name().to_string()));
+    keys
+}
+
+impl SettingsSync {
+    fn restore() -> SettingsSync {
+        let local_storage = window().local_storage().unwrap().unwrap();
+        let state = match local_storage.get("settings_sync") {
+            Ok(Some(json)) => serde_json::from_str(&json).unwrap_or_default(),
+            _ => SyncState::default(),
+        };
+        SettingsSync {
+            state: Mutex::new(state),
+            pushing: AtomicBool::new(false),
+            unavailable: AtomicBool::new(false),
+        }
+    }
+
+    fn save(state: &SyncState) {
+        let local_storage = window().local_storage().unwrap().unwrap();
+        local_storage.set("settings_sync", &serde_json::to_string(state).unwrap()).unwrap();
+    }
+
+    /// Remember that a key changed locally, so that it is sent with the next push
+    pub fn record(&self, key: impl Into<String>) {
+        let key = key.into();
+        let mut state = self.state.lock().unwrap();
+        state.modified.insert(key.clone(), now());
+        state.pending.insert(key);
+        Self::save(&state);
+    }
+
+    /// Whether some changes haven't reached the server yet, and can reach it
+    pub fn has_pending(&self) -> bool {
+        !self.unavailable.load(Ordering::Relaxed) && !self.state.lock().unwrap().pending.is_empty()
+    }
+
+    /// Stop syncing when the server has no `settings` endpoint. Changes stay pending for a server that has one.
+    fn check_available(&self, error: &ApiError) -> bool {
+        if error.is_not_found() {
+            log!("Settings sync is unavailable");
+            self.unavailable.store(true, Ordering::Relaxed);
+            return false;
+        }
+        true
+    }
+
+    pub fn fetch(&'static self, app_link: AppLink) {
+        spawn_local(async move {
+            match api_get::<SyncedSettings>("settings").await {
+                Ok(values) => app_link.send_message(AppMsg::SettingsSyncSuccess(values)),
+                Err(e) if !self.check_available(&e) => app_link.send_message(AppMsg::SettingsSyncChanged),
+                Err(e) => app_link.send_message(AppMsg::ApiFailure(e)),
+            }
+        });
+    }
+
+    /// Merge the values of the server, keeping the most recent change of each key
+    pub fn merge(&self, remote_values: SyncedSettings) {
+        let mut state = self.state.lock().unwrap();
+        for (key, remote) in &remote_values {
+            if state.modified.get(key).map(|modified| *modified >= remote.modified).unwrap_or(false) {
+                continue;
+            }
+            if apply_remote(key, &remote.value) {
+                state.modified.insert(key.clone(), remote.modified);
+                state.pending.remove(key);
+            }
+        }
+
+        // Values set before syncing existed are sent, unless the server already has the key
+        for key in local_keys() {
+            if !remote_values.contains_key(&key) && !state.modified.contains_key(&key) {
+                state.modified.insert(key.clone(), now());
+                state.pending.insert(key);
+            }
+        }
+        Self::save(&state);
+    }
+
+    /// Send the pending changes to the server
+    pub async fn push_pending(&self) -> Result<(), ApiError> {
+        let values = {
+            let state = self.state.lock().unwrap();
+            state.pending.iter().filter_map(|key| {
+                let value = current_value(key)?;
+                Some((key.clone(), SyncedValue { value, modified: state.modified.get(key).copied().unwrap_or_default() }))
+            }).collect::<SyncedSettings>()
+        };
+        if values.is_empty() || self.unavailable.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        match api_post(&values, "settings").await {
+            Err(e) if !self.check_available(&e) => return Ok(()),
+            result => result?,
+        }
+
+        // Keys that changed again during the request stay pending
+        let mut state = self.state.lock().unwrap();
+        for (key, value) in values {
+            if state.modified.get(&key) == Some(&value.modified) {
+                state.pending.remove(&key);
+            }
+        }
+        Self::save(&state);
+        Ok(())
+    }
+
+    /// Push the pending changes in the background, notifying the app when the push starts and ends
+    pub fn push(&'static self, app_link: AppLink) {
+        if !self.has_pending() || self.pushing.swap(true, Ordering::Relaxed) {
+            return;
+        }
+        app_link.send_message(AppMsg::SettingsSyncChanged);
+        spawn_local(async move {
+            if let Err(e) = self.push_pending().await {
+                e.handle_api_error();
+            }
+            self.pushing.store(false, Ordering::Relaxed);
+            app_link.send_message(AppMsg::SettingsSyncChanged);
+        });
+    }
+}
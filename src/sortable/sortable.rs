@@ -25,6 +25,9 @@ pub struct Sortable {
     /// Yew doesn't update elements when it thinks they're the same. Except we modify the style property by hand so we need to add a key that changes every time we want yew to update the element.
     reload_id: usize,
 
+    /// Item moved with the keyboard, which gets the focus back once rendered at its new position
+    focused: Option<usize>,
+
     // Event handlers
     on_mouse_down: wasm_bindgen::prelude::Closure<dyn std::ops::FnMut(web_sys::MouseEvent)>,
     on_touch_start: wasm_bindgen::prelude::Closure<dyn std::ops::FnMut(web_sys::TouchEvent)>,
@@ -35,6 +38,8 @@ pub struct Sortable {
 
 pub enum SortableMsg {
     ChangeOrder(Vec<usize>),
+    /// Keyboard event on an item, which is moved up or down with the arrows
+    Key(usize, KeyboardEvent),
 }
 
 impl Component for Sortable {
@@ -164,6 +169,7 @@ impl Component for Sortable {
             id,
             order,
             reload_id: 0,
+            focused: None,
             on_mouse_down,
             on_touch_start,
             on_mouse_move,
@@ -182,6 +188,28 @@ impl Component for Sortable {
                 self.reload_id += 1;
                 true
             }
+            SortableMsg::Key(item, event) => {
+                let delta = match event.key().as_str() {
+                    "ArrowUp" => -1,
+                    "ArrowDown" => 1,
+                    _ => return false,
+                };
+                event.prevent_default();
+                let mut order = self.order.borrow().clone();
+                let Some(position) = order.iter().position(|&i| i == item) else { return false };
+                let new_position = position as isize + delta;
+                if new_position < 0 || new_position >= order.len() as isize {
+                    return false;
+                }
+                let new_position = new_position as usize;
+                order.remove(position);
+                order.insert(new_position, item);
+                let name = ctx.props().items.get(item).cloned().unwrap_or_default();
+                announce(&tf("{item} déplacé en position {position} sur {count}", &[("item", &name), ("position", &(new_position + 1)), ("count", &order.len())]));
+                self.focused = Some(item);
+                ctx.link().send_message(SortableMsg::ChangeOrder(order));
+                false
+            }
         }
     }
 
@@ -190,20 +218,34 @@ impl Component for Sortable {
         true
     }
 
+    fn rendered(&mut self, _ctx: &Context<Self>, _first_render: bool) {
+        if let Some(item) = self.focused.take() {
+            let el = window().doc().get_element_by_id(&format!("sortable-{}-{item}", self.id));
+            if let Some(el) = el.and_then(|el| el.dyn_into::<HtmlElement>().ok()) {
+                let _ = el.focus();
+            }
+        }
+    }
+
     fn view(&self, ctx: &Context<Self>) -> Html {
-        let items = self.order.borrow().iter().map(|i| {
+        let count = ctx.props().items.len();
+        let items = self.order.borrow().iter().enumerate().map(|(position, i)| {
             let item = ctx.props().items.get(*i).unwrap();
             let fid = format!("sortable-{}-{}", self.id, i);
+            let label = tf("{item}, position {position} sur {count}", &[("item", item), ("position", &(position + 1)), ("count", &count)]);
+            let i = *i;
+            let onkeydown = ctx.link().callback(move |e| SortableMsg::Key(i, e));
             html! {
-                <div class="sortable-item" id={fid} style={format!("top: 0px; transition: unset; reload-id: {};", self.reload_id)}>
-                    <svg xmlns="http://www.w3.org/2000/svg" width="20" height="20" viewBox="0 0 20 20"><path d="M2 11h16v2H2zm0-4h16v2H2zm8 11l3-3H7l3 3zm0-16L7 5h6l-3-3z"/></svg>
+                <div class="sortable-item" id={fid} role="listitem" tabindex="0" aria-label={label} aria-describedby={format!("sortable-{}-help", self.id)} {onkeydown} style={format!("top: 0px; transition: unset; reload-id: {};", self.reload_id)}>
+                    <svg aria-hidden="true" xmlns="http://www.w3.org/2000/svg" width="20" height="20" viewBox="0 0 20 20"><path d="M2 11h16v2H2zm0-4h16v2H2zm8 11l3-3H7l3 3zm0-16L7 5h6l-3-3z"/></svg>
                     {item}
                 </div>
             }
         }).collect::<Html>();
 
         html! {
-            <div class="sortable">
+            <div class="sortable" role="list">
+                <span id={format!("sortable-{}-help", self.id)} class="visually-hidden">{t("Utilisez les flèches haut et bas pour déplacer un élément")}</span>
                 {items}
             </div>
         }
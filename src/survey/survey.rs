@@ -8,6 +8,7 @@ impl HackTraitGetLocalizedText for HashMap<String, String> {
     fn get_localized(&self, lang: &str) -> Option<String> {
         if let Some(best) = self.get(lang).cloned() { return Some(best) }
         if let Some(default) = self.get("").cloned() { return Some(default) }
+        if let Some(english) = self.get("en").cloned() { return Some(english) }
         if let Some(random_key) = self.keys().next() { return self.get(random_key).cloned() }
         None
     }
@@ -284,9 +285,8 @@ impl Component for SurveyComp {
         let opt_description = ctx.props().survey.description.get_localized(l);
         let survey_lenght = slides.len() + 1;
 
-        // TODO: Translations
-        let back_msg = if self.progress == 0 { "Fermer" } else { "Précédent" };
-        let next_msg = if self.progress == 0 { "Commencer" } else if self.progress == survey_lenght - 1 { "Terminer" } else { "Suivant" };
+        let back_msg = if self.progress == 0 { t("Fermer") } else { t("Précédent") };
+        let next_msg = if self.progress == 0 { t("Commencer") } else if self.progress == survey_lenght - 1 { t("Terminer") } else { t("Suivant") };
 
         let next_disabled = (1..survey_lenght).contains(&self.progress) && ctx.props().survey.questions[self.progress - 1].required && match self.answers[self.progress - 1] {
             Some(Answer::Input(ref data)) => data.is_empty(),
@@ -1,34 +1,123 @@
-use crate::prelude::*;
+//! Translation of the interface, using the gettext catalogs of the `locales` directory.
+//!
+//! Catalogs are embedded at build time by `build.rs`, which also fails when a catalog doesn't translate a message.
+//! Messages are identified by their French text, which is displayed when there is no translation.
+//! Placeholders such as `{name}` are replaced by the arguments of [`tf`], [`tn`] and [`tord`].
+//! Translations of date and time formats are checked when catalogs are loaded, as chrono panics when formatting with an invalid one.
 
+use std::fmt::Display;
+use chrono::format::{Item, StrftimeItems};
+use crate::{po::{Catalog, ORDINAL_CONTEXT}, prelude::*};
+
+include!(concat!(env!("OUT_DIR"), "/catalogs.rs"));
+
+lazy_static! {
+    static ref CATALOGS: HashMap<&'static str, Catalog> = CATALOG_SOURCES.iter().filter_map(|(locale, source)| match Catalog::parse(source) {
+        Ok(mut catalog) => {
+            catalog.messages.retain(|(_, id), forms| {
+                let valid = !is_valid_format(id) || forms.iter().all(|form| is_valid_format(form));
+                if !valid {
+                    log!("Invalid format in catalog {locale}: {forms:?}");
+                }
+                valid
+            });
+            Some((*locale, catalog))
+        },
+        Err(e) => {
+            log!("Invalid catalog {locale}: {e}");
+            None
+        }
+    }).collect();
+}
+
+/// Whether a message is a chrono format string, such as the ones of [`format_time`]. Untranslated formats fall back to their message id.
+fn is_valid_format(format: &str) -> bool {
+    format.contains('%') && !StrftimeItems::new(format).any(|item| matches!(item, Item::Error))
+}
+
+lazy_static! {
+    /// Locales that have a catalog, with the name of their language
+    pub static ref LOCALES: Vec<(&'static str, &'static str)> = CATALOG_SOURCES.iter().map(|(locale, _)| {
+        let name = CATALOGS.get(locale).and_then(|c| c.headers.get("X-Language-Name")).map(|n| n.as_str()).unwrap_or(*locale);
+        (*locale, name)
+    }).collect();
+}
+
+/// Locale used when none of the languages of the user is available
+pub const DEFAULT_LOCALE: &str = "fr";
+
+/// Find the best available locale for a list of language tags in order of preference, such as `navigator.languages`.
+/// Tags match a locale exactly or by their primary language, so that `de-CH` gets `de`.
+pub fn negotiate_locale(languages: impl IntoIterator<Item = String>) -> Option<&'static str> {
+    for language in languages {
+        let language = language.to_ascii_lowercase().replace('_', "-");
+        if let Some((locale, _)) = LOCALES.iter().find(|(locale, _)| *locale == language) {
+            return Some(locale);
+        }
+        let primary = language.split('-').next().unwrap_or_default();
+        if let Some((locale, _)) = LOCALES.iter().find(|(locale, _)| locale.split('-').next() == Some(primary)) {
+            return Some(locale);
+        }
+    }
+    None
+}
+
+fn catalog() -> Option<&'static Catalog> {
+    CATALOGS.get(SETTINGS.locale())
+}
+
+/// A field of the header of the catalog of the current language
+pub fn locale_header(name: &str) -> Option<&'static str> {
+    catalog()?.headers.get(name).map(|value| value.as_str())
+}
+
+fn translations(context: Option<&str>, id: &str) -> Option<&'static Vec<String>> {
+    catalog()?.messages.get(&(context.map(String::from), id.to_string()))
+}
+
+fn interpolate(text: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut result = text.to_string();
+    for (name, value) in args {
+        result = result.replace(&format!("{{{name}}}"), &value.to_string());
+    }
+    result
+}
+
+/// Translate a message
 pub fn t(s: &'static str) -> &'static str {
-    if SETTINGS.lang() == Lang::French {
-        return s;
+    match translations(None, s).and_then(|t| t.first()) {
+        Some(translation) => translation.as_str(),
+        None => s,
     }
-    
-    match s {
-        "Janvier" => "January",
-        "Février" => "February",
-        "Mars" => "March",
-        "Avril" => "April",
-        "Mai" => "May",
-        "Juin" => "June",
-        "Juillet" => "July",
-        "Août" => "August",
-        "Septembre" => "September",
-        "Octobre" => "October",
-        "Novembre" => "November",
-        "Décembre" => "December",
-        "Lundi" => "Monday",
-        "Mardi" => "Tuesday",
-        "Mercredi" => "Wednesday",
-        "Jeudi" => "Thursday",
-        "Vendredi" => "Friday",
-        "Samedi" => "Saturday",
-        "Dimanche" => "Sunday",
-        
-        s => {
-            log!("Untranslated string: {}", s);
-            s
-        }
+}
+
+/// Translate a message and fill its placeholders
+pub fn tf(s: &'static str, args: &[(&str, &dyn Display)]) -> String {
+    interpolate(t(s), args)
+}
+
+/// Pick the form matching `n` among the translations, falling back to the singular and plural of the message id
+fn select_form(forms: Option<&'static Vec<String>>, n: u64, ordinal: bool, singular: &'static str, plural: &'static str) -> &'static str {
+    let index = catalog().map(|c| c.plural_form(n, ordinal)).unwrap_or((n != 1) as usize);
+    match forms {
+        Some(forms) => forms.get(index).or_else(|| forms.last()).map(|f| f.as_str()).unwrap_or(singular),
+        None if index == 0 => singular,
+        None => plural,
     }
 }
+
+/// Translate a message depending on a quantity, available as the `{n}` placeholder
+pub fn tn(singular: &'static str, plural: &'static str, n: u64, args: &[(&str, &dyn Display)]) -> String {
+    let form = select_form(translations(None, singular), n, false, singular, plural);
+    let mut args: Vec<(&str, &dyn Display)> = args.to_vec();
+    args.push(("n", &n));
+    interpolate(form, &args)
+}
+
+/// Translate a message containing the ordinal number `{n}`, such as a day of the month
+pub fn tord(s: &'static str, n: u64, args: &[(&str, &dyn Display)]) -> String {
+    let form = select_form(translations(Some(ORDINAL_CONTEXT), s), n, true, s, s);
+    let mut args: Vec<(&str, &dyn Display)> = args.to_vec();
+    args.push(("n", &n));
+    interpolate(form, &args)
+}
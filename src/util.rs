@@ -29,6 +29,51 @@ pub fn window() -> web_sys::Window {
     }
 }
 
+/// Time after which the URL of a downloaded file is revoked, as some browsers start downloads asynchronously
+const DOWNLOAD_URL_LIFETIME: std::time::Duration = std::time::Duration::from_secs(40);
+
+/// Replace the characters that aren't allowed in file names on common systems
+pub fn safe_filename(name: &str) -> String {
+    let name = name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '-',
+            c if c.is_control() => '-',
+            c => c,
+        })
+        .collect::<String>();
+    name.trim_matches(|c: char| c == '.' || c.is_whitespace()).to_string()
+}
+
+/// Make the browser download a text file
+pub fn download_file(filename: &str, mime_type: &str, content: &str) -> Result<(), JsValue> {
+    download_blob(filename, mime_type, &JsValue::from_str(content))
+}
+
+/// Make the browser download a binary file
+pub fn download_bytes(filename: &str, mime_type: &str, content: &[u8]) -> Result<(), JsValue> {
+    download_blob(filename, mime_type, &js_sys::Uint8Array::from(content))
+}
+
+fn download_blob(filename: &str, mime_type: &str, content: &JsValue) -> Result<(), JsValue> {
+    let mut options = BlobPropertyBag::new();
+    options.type_(mime_type);
+    let parts = Array::of1(content);
+    let blob = Blob::new_with_str_sequence_and_options(&parts, &options)?;
+    let url = Url::create_object_url_with_blob(&blob)?;
+
+    let doc = window().doc();
+    let anchor: HtmlAnchorElement = doc.create_element("a")?.dyn_into()?;
+    anchor.set_href(&url);
+    anchor.set_download(filename);
+    anchor.click();
+
+    spawn_local(async move {
+        sleep(DOWNLOAD_URL_LIFETIME).await;
+        let _ = Url::revoke_object_url(&url);
+    });
+    Ok(())
+}
+
 #[deprecated(note = "Use now() instead")]
 pub fn now_ts() -> i64 {
     now()
@@ -91,22 +136,19 @@ impl Iterator for HtmlCollectionIter {
 }
 
 // Check if there are events on the specified day of the current week
-pub fn has_event_on_day(events: &Vec<RawEvent>, current_day: NaiveDate, day_to_look: Weekday) -> bool {
+pub fn has_event_on_day(events: &Vec<RawEvent>, imported_events: &[ImportedEvent], current_day: NaiveDate, day_to_look: Weekday) -> bool {
     let offset_to_saturday = day_to_look.num_days_from_monday() as i64 - current_day.weekday().number_from_monday() as i64 + 1;
     let saturday_date = current_day + chrono::Duration::days(offset_to_saturday);
-    let saturday_ts = Paris.from_local_datetime(&saturday_date.and_hms_opt(0, 0, 0).unwrap()).unwrap().timestamp() as u64;
+    let saturday_ts = day_start(saturday_date);
     let range = saturday_ts..saturday_ts + 3600*24;
-    if events.is_empty() { return false }
-    match events.binary_search_by(|e| e.start_unixtime.cmp(&saturday_ts)) {
-        Ok(_) => true,
-        Err(i) => {
-            if i < events.len() {
-                range.contains(&events[i].start_unixtime)
-            } else {
-                false
-            }
-        },
-    }
+    let last_imported = imported_events.partition_point(|e| e.start_unixtime < range.end);
+    starts_in_range(events, &range, |e| e.start_unixtime) || imported_events[..last_imported].iter().any(|e| e.overlaps(&range))
+}
+
+/// Check if an event of a list sorted by start time starts in the range
+fn starts_in_range<T>(events: &[T], range: &std::ops::Range<u64>, start: impl Fn(&T) -> u64) -> bool {
+    let i = events.partition_point(|e| start(e) < range.start);
+    events.get(i).map(|e| range.contains(&start(e))).unwrap_or(false)
 }
 
 pub trait HackTraitProfileUrl {
@@ -122,62 +164,3 @@ impl HackTraitProfileUrl for UserDesc {
 pub fn now() -> i64 {
     (js_sys::Date::new_0().get_time() / 1000.0) as i64
 }
-
-pub fn format_time_diff(diff: i64) -> String {
-    let french = SETTINGS.lang() == Lang::French;
-    if diff < 60 {
-        match (french, diff >= 2) {
-            (true, true) => format!("{diff} secondes"),
-            (true, false) => format!("{diff} seconde"),
-            (false, true) => format!("{diff} seconds ago"),
-            (false, false) => format!("{diff} second ago"),
-        }
-    } else if diff < 3600 {
-        let diff = diff / 60;
-        match (french, diff >= 2) {
-            (true, true) => format!("{diff} minutes"),
-            (true, false) => format!("{diff} minute"),
-            (false, true) => format!("{diff} minutes ago"),
-            (false, false) => format!("{diff} minute ago"),
-        }
-    } else if diff < 86400 {
-        let diff = diff / 3600;
-        match (french, diff >= 2) {
-            (true, true) => format!("{diff} heures"),
-            (true, false) => format!("{diff} heure"),
-            (false, true) => format!("{diff} hours ago"),
-            (false, false) => format!("{diff} hour ago"),
-        }
-    } else if diff < 7*86400 {
-        let diff = diff / 86400;
-        match (french, diff >= 2) {
-            (true, true) => format!("{diff} jours"),
-            (true, false) => format!("{diff} jour"),
-            (false, true) => format!("{diff} days ago"),
-            (false, false) => format!("{diff} day ago"),
-        }
-    } else if diff < 30*86400 {
-        let diff = diff / (7*86400);
-        match (french, diff >= 2) {
-            (true, true) => format!("{diff} semaines"),
-            (true, false) => format!("{diff} semaine"),
-            (false, true) => format!("{diff} weeks ago"),
-            (false, false) => format!("{diff} week ago"),
-        }
-    } else if diff < 365*86400 {
-        let diff = diff / (30*86400);
-        match (french, diff >= 2) {
-            (true, _) => format!("{diff} mois"),
-            (false, true) => format!("{diff} months ago"),
-            (false, false) => format!("{diff} month ago"),
-        }
-    } else {
-        let diff = diff / (365*86400);
-        match (french, diff >= 2) {
-            (true, true) => format!("{diff} ans"),
-            (true, false) => format!("{diff} an"),
-            (false, true) => format!("{diff} years ago"),
-            (false, false) => format!("{diff} year ago"),
-        }
-    }
-}